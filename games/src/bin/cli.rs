@@ -0,0 +1,226 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use games::engine::difficulty::Difficulty;
+use games::engine::history::{History, Move};
+use games::engine::solver::{self, Hint};
+use games::engine::topology::Topology;
+use games::engine::{Board, BoardCell, GameId, GameState};
+use strum::IntoEnumIterator;
+
+// Minesweeper in the terminal, on the same engine as the web version, for
+// playing and poking at it without a browser.
+
+const USAGE: &str = "Usage: cli [easy|medium|hard|extreme|<rows>x<cols>x<mines>|<game id>]";
+
+const HELP: &str = "\
+Rows and columns count from 1.
+  <row> <col>      reveal a cell, or chord around a number
+  f <row> <col>    flag a cell (again for a question mark)
+  c <row> <col>    chord around a number
+  u                undo
+  y                redo
+  h                hint
+  n [game]         new game, same options as on the command line
+  ?                this help
+  q                quit";
+
+// A preset name, a custom size with a random seed, or a full game id.
+fn game_id(arg: &str) -> Option<GameId> {
+    let preset = Difficulty::iter()
+        .filter(|diff| *diff != Difficulty::Custom)
+        .find(|diff| diff.to_string().eq_ignore_ascii_case(arg));
+    let size = match preset {
+        Some(diff) => {
+            let (rows, cols, mines) = diff.value();
+            format!("{}x{}x{}", rows, cols, mines)
+        }
+        None => arg.to_string(),
+    };
+    arg.parse()
+        .or_else(|_| format!("{}-{:x}", size, rand::random::<u64>()).parse())
+        .ok()
+}
+
+fn symbol(cell: &BoardCell) -> String {
+    match (cell.flags(), cell.mines(), cell.value()) {
+        (1, _, _) => ".".to_string(),
+        (2, _, _) => match cell.flagged_mines() {
+            1 => "F".to_string(),
+            n => format!("F{}", n),
+        },
+        (3, _, _) => "?".to_string(),
+        (4, _, _) => "X".to_string(),
+        (_, 0, 0) => " ".to_string(),
+        (_, 0, n) => n.to_string(),
+        (_, 1, _) => "*".to_string(),
+        (_, n, _) => format!("*{}", n),
+    }
+}
+
+// The board with row and column numbers, odd rows of a hex board pushed
+// half a cell to the right.
+fn render(board: &Board) -> String {
+    let mut out = String::from("    ");
+    for y in 1..=board.cols {
+        out.push_str(&format!("{:>3}", y));
+    }
+    for x in 0..board.rows as usize {
+        out.push_str(&format!("\n{:>3} ", x + 1));
+        if board.topology == Topology::Hex && x % 2 == 1 {
+            out.push(' ');
+        }
+        for y in 0..board.cols as usize {
+            out.push_str(&format!("{:>3}", symbol(board.cell(x, y))));
+        }
+    }
+    out
+}
+
+// Turns 1-based coordinates into a cell on the board.
+fn cell(board: &Board, row: &str, col: &str) -> Option<(usize, usize)> {
+    let (row, col) = (row.parse::<usize>().ok()?, col.parse::<usize>().ok()?);
+    ((1..=board.rows as usize).contains(&row) && (1..=board.cols as usize).contains(&col))
+        .then(|| (row - 1, col - 1))
+}
+
+struct Game {
+    board: Board,
+    history: History,
+    start: Option<Instant>,
+}
+
+impl Game {
+    fn new(id: GameId) -> Self {
+        println!("Game {}", id);
+        Game {
+            board: Board::from(id),
+            history: History::new(false),
+            start: None,
+        }
+    }
+
+    fn play(&mut self, mv: Move) {
+        if self.board.game_state != GameState::InProgress {
+            println!("The game is over, n for a new one");
+            return;
+        }
+        self.history.play(&mut self.board, mv);
+        if self.board.started() && self.start.is_none() {
+            self.start = Some(Instant::now());
+        }
+    }
+
+    fn status(&self) -> String {
+        let seconds = self
+            .start
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        match self.board.game_state {
+            GameState::InProgress => format!("{} mines left", self.board.mines_left()),
+            GameState::Won => format!("Cleared in {:.2}s!", seconds),
+            GameState::Lost => format!("Boom, after {:.2}s", seconds),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let arg = env::args().nth(1).unwrap_or_else(|| "easy".to_string());
+    if arg == "-h" || arg == "--help" {
+        println!("{}\n\n{}", USAGE, HELP);
+        return ExitCode::SUCCESS;
+    }
+    let Some(id) = game_id(&arg) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let mut game = Game::new(id);
+    println!("? for help");
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        println!("{}\n{}", render(&game.board), game.status());
+        print!("> ");
+        // Nothing to do about a closed terminal but stop at the next read
+        let _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            return ExitCode::SUCCESS;
+        };
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            [] => (),
+            ["q"] => return ExitCode::SUCCESS,
+            ["?"] => println!("{}", HELP),
+            ["u"] => game.history.undo(&mut game.board),
+            ["y"] => game.history.redo(&mut game.board),
+            ["h"] => match solver::hint(&game.board) {
+                hint @ (Hint::Safe((x, y), _) | Hint::Opening((x, y))) => {
+                    println!("Row {} col {}: {}", x + 1, y + 1, hint)
+                }
+                hint => println!("{}", hint),
+            },
+            ["n"] => {
+                game = Game::new(GameId {
+                    seed: rand::random(),
+                    ..game.board.game_id()
+                })
+            }
+            ["n", arg] => match game_id(arg) {
+                Some(id) => game = Game::new(id),
+                None => println!("{}", USAGE),
+            },
+            [row, col] | ["r", row, col] => match cell(&game.board, row, col) {
+                Some((x, y)) => game.play(Move::click(&game.board, x, y)),
+                None => println!("No such cell"),
+            },
+            ["f", row, col] => match cell(&game.board, row, col) {
+                Some((x, y)) => game.play(Move::Flag(x, y)),
+                None => println!("No such cell"),
+            },
+            ["c", row, col] => match cell(&game.board, row, col) {
+                Some((x, y)) if game.board.cell(x, y).flags() == 0 => game.play(Move::Chord(x, y)),
+                Some(_) => println!("Only numbers can be chorded"),
+                None => println!("No such cell"),
+            },
+            _ => println!("Unknown command, ? for help"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_ids() {
+        let id = game_id("Hard").unwrap();
+        assert_eq!((id.rows, id.cols, id.mines), (16, 30, 99));
+        let id = game_id("10x12x20").unwrap();
+        assert_eq!((id.rows, id.cols, id.mines), (10, 12, 20));
+        assert_eq!(game_id("9x9x10-1f-n").unwrap().to_string(), "9x9x10-1f-n");
+        assert_eq!(game_id("tiny"), None);
+        assert_eq!(game_id("9x9x80"), None);
+    }
+
+    #[test]
+    fn cells_count_from_one() {
+        let board = Board::with_seed(9, 12, 10, 0);
+        assert_eq!(cell(&board, "1", "12"), Some((0, 11)));
+        assert_eq!(cell(&board, "0", "1"), None);
+        assert_eq!(cell(&board, "10", "1"), None);
+        assert_eq!(cell(&board, "a", "1"), None);
+    }
+
+    #[test]
+    fn rendering() {
+        let mut board = Board::with_mines(2, 3, &[(0, 0)]);
+        board.flag(0, 0);
+        board.click(1, 2);
+        assert_eq!(
+            render(&board),
+            "      1  2  3\n  1   F  1   \n  2   .  1   "
+        );
+        board.topology = Topology::Hex;
+        assert!(render(&board).ends_with("\n  2    .  1   "));
+    }
+}
@@ -0,0 +1,1048 @@
+pub mod avf;
+pub mod difficulty;
+pub mod history;
+pub mod metrics;
+pub mod probability;
+pub mod rawvf;
+pub mod replay;
+pub mod safety;
+pub mod solver;
+pub mod text;
+pub mod topology;
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use safety::Safety;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use topology::Topology;
+
+// Minesweeper game logic, kept free of any browser/Yew dependency so it can be
+// driven from the web view as well as from native tools and tests.
+
+#[derive(Clone, PartialEq, Debug, Copy, Serialize, Deserialize)]
+pub enum GameState {
+    InProgress,
+    Won,
+    Lost,
+}
+
+// Each cell packs, from the low bits up: the number of mines around it
+// (5 bits, up to 3 on each of 8 neighbours), the mines in the cell itself
+// (2 bits), its state (3 bits: 0 revealed, 1 hidden, 2 flagged, 3 question
+// mark, 4 exploded mine) and how many mines it is flagged for (2 bits).
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardCell {
+    pub cell: u16,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl fmt::Display for BoardCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let out = match self.flags() {
+            3 => String::from("?"),
+            2 => match self.flagged_mines() {
+                1 => String::from("f"),
+                n => format!("f{}", n),
+            },
+            1 => String::from(" "),
+            0 => match (self.mines(), self.value()) {
+                (0, 0) => String::from(" "),
+                (0, n) => n.to_string(),
+                (1, _) => String::from("m"),
+                (n, _) => format!("m{}", n),
+            },
+            _ => String::from("e"),
+        };
+        write!(f, "{}", out)
+    }
+}
+
+impl fmt::Debug for BoardCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let out = format!(
+            "{:?}: ({}, {}, {})",
+            (self.x, self.y),
+            self.value(),
+            self.mines(),
+            self.flags()
+        );
+        write!(f, "{}", out)
+    }
+}
+
+impl BoardCell {
+    fn hidden(x: usize, y: usize) -> Self {
+        let mut cell = BoardCell { cell: 0, x, y };
+        cell.set_state(1);
+        cell
+    }
+    pub fn flags(&self) -> u8 {
+        (self.cell >> 7 & 0b111) as u8
+    }
+    pub fn value(&self) -> u8 {
+        (self.cell & 0b11111) as u8
+    }
+    pub fn mines(&self) -> u8 {
+        (self.cell >> 5 & 0b11) as u8
+    }
+    pub fn is_mine(&self) -> bool {
+        self.mines() != 0
+    }
+    pub fn flagged_mines(&self) -> u8 {
+        match self.flags() {
+            2 => (self.cell >> 10 & 0b11) as u8,
+            _ => 0,
+        }
+    }
+    // Also drops the flag count, which only means something while flagged
+    fn set_state(&mut self, state: u8) {
+        self.cell = (self.cell & 0b111_1111) | (state as u16) << 7;
+    }
+    fn set_flagged(&mut self, mines: u8) {
+        self.set_state(2);
+        self.cell |= (mines as u16) << 10;
+    }
+    fn set_value(&mut self, value: u8) {
+        self.cell = (self.cell & !0b11111) | value as u16;
+    }
+    fn set_mines(&mut self, mines: u8) {
+        self.cell = (self.cell & !(0b11 << 5)) | (mines as u16) << 5;
+    }
+    fn click(&mut self) -> bool {
+        if self.flags() == 1 {
+            self.set_state(0);
+            if self.value() == 0 && !self.is_mine() {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Hidden, flagged for 1 up to `max` mines, question mark, hidden again.
+    // Returns the change in flagged mines.
+    fn flag(&mut self, max: u8) -> i8 {
+        let before = self.flagged_mines() as i8;
+        match (self.flags(), self.flagged_mines()) {
+            (1, _) => self.set_flagged(1),
+            (2, n) if n < max => self.set_flagged(n + 1),
+            (2, _) => self.set_state(3),
+            (3, _) => self.set_state(1),
+            _ => (),
+        }
+        self.flagged_mines() as i8 - before
+    }
+}
+
+// Everything needed to regenerate a board: the same id and the same first
+// click always produce the same mine layout.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct GameId {
+    pub rows: u16,
+    pub cols: u16,
+    pub mines: u16,
+    pub seed: u64,
+    pub no_guess: bool,
+    pub topology: Topology,
+    pub wrap: bool,
+    pub multi: bool,
+    pub safety: Safety,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseGameIdError;
+
+impl fmt::Display for ParseGameIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected a game id like 9x9x10-1f2e3d4c")
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}x{}-{:x}",
+            self.rows, self.cols, self.mines, self.seed
+        )?;
+        if self.no_guess {
+            write!(f, "-n")?;
+        }
+        if self.topology == Topology::Hex {
+            write!(f, "-h")?;
+        }
+        if self.wrap {
+            write!(f, "-w")?;
+        }
+        if self.multi {
+            write!(f, "-m")?;
+        }
+        match self.safety {
+            Safety::Unprotected => write!(f, "-u")?,
+            Safety::SafeCell => write!(f, "-c")?,
+            Safety::SafeOpening => (),
+            Safety::ZeroOpening => write!(f, "-z")?,
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameId {
+    type Err = ParseGameIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('-');
+        let (size, seed) = match (parts.next(), parts.next()) {
+            (Some(size), Some(seed)) => (size, seed),
+            _ => return Err(ParseGameIdError),
+        };
+        let (mut no_guess, mut topology, mut wrap, mut multi) =
+            (false, Topology::Square, false, false);
+        let mut safety = None;
+        for part in parts {
+            match part {
+                "n" if !no_guess => no_guess = true,
+                "h" if topology == Topology::Square => topology = Topology::Hex,
+                "w" if !wrap => wrap = true,
+                "m" if !multi => multi = true,
+                "u" if safety.is_none() => safety = Some(Safety::Unprotected),
+                "c" if safety.is_none() => safety = Some(Safety::SafeCell),
+                "z" if safety.is_none() => safety = Some(Safety::ZeroOpening),
+                _ => return Err(ParseGameIdError),
+            }
+        }
+        let size = size
+            .split('x')
+            .map(|n| n.parse::<u16>().map_err(|_| ParseGameIdError))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (rows, cols, mines) = match size[..] {
+            [rows, cols, mines] => (rows, cols, mines),
+            _ => return Err(ParseGameIdError),
+        };
+        let seed = u64::from_str_radix(seed, 16).map_err(|_| ParseGameIdError)?;
+        if rows > MAX_SIDE || cols > MAX_SIDE {
+            return Err(ParseGameIdError);
+        }
+        let area = rows as u32 * cols as u32;
+        let per_cell = if multi { MAX_CELL_MINES as u32 } else { 1 };
+        if area < 9 || mines as u32 > (area - 9) * per_cell {
+            return Err(ParseGameIdError);
+        }
+        Ok(GameId {
+            rows,
+            cols,
+            mines,
+            seed,
+            no_guess,
+            topology,
+            wrap,
+            multi,
+            safety: safety.unwrap_or_default(),
+        })
+    }
+}
+
+// How many times a no guess layout gets reworked before settling for whatever
+// it has. Counted in attempts rather than time so that a game id always
+// regenerates the same board.
+const NO_GUESS_ATTEMPTS: usize = 500;
+
+// Most mines a single cell can hold on a multi-mine board.
+pub const MAX_CELL_MINES: u8 = 3;
+
+// Most rows or columns a game id can ask for, as many as the menu goes up to.
+pub const MAX_SIDE: u16 = 60;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Board {
+    board: Vec<Vec<BoardCell>>,
+    pub rows: u16,
+    pub cols: u16,
+    pub mines: u16,
+    pub seed: u64,
+    pub no_guess: bool,
+    pub topology: Topology,
+    // Neighbours carry on across the edges to the other side
+    pub wrap: bool,
+    // Cells can hold up to MAX_CELL_MINES mines each
+    pub multi: bool,
+    pub safety: Safety,
+    pub game_state: GameState,
+    start: bool,
+    clicked_cells: usize,
+    flagged_mines: i16,
+    pub flag: bool,
+}
+
+impl Board {
+    pub fn new(rows: u16, cols: u16, mines: u16) -> Self {
+        Self::with_seed(rows, cols, mines, rand::random())
+    }
+
+    pub fn with_seed(rows: u16, cols: u16, mines: u16, seed: u64) -> Self {
+        Board {
+            board: (0..rows as _)
+                .map(|x| (0..cols as _).map(|y| BoardCell::hidden(x, y)).collect())
+                .collect(),
+            rows,
+            cols,
+            mines,
+            seed,
+            no_guess: false,
+            topology: Topology::Square,
+            wrap: false,
+            multi: false,
+            safety: Safety::default(),
+            game_state: GameState::InProgress,
+            start: false,
+            clicked_cells: 0,
+            flagged_mines: 0,
+            flag: false,
+        }
+    }
+
+    // A board already laid out with mines at the given places, for positions
+    // that didn't come from a seed.
+    pub fn with_mines(rows: u16, cols: u16, mines: &[(usize, usize)]) -> Self {
+        let mut board = Self::with_seed(rows, cols, mines.len() as u16, 0);
+        board.place_mines(mines);
+        board
+    }
+
+    // Lays the mines out by hand instead of from the seed, a cell listed more
+    // than once holds as many mines.
+    pub fn place_mines(&mut self, mines: &[(usize, usize)]) {
+        for cell in self.board.iter_mut().flatten() {
+            *cell = BoardCell::hidden(cell.x, cell.y);
+        }
+        for &(x, y) in mines {
+            let cell = &mut self.board[x][y];
+            cell.set_mines((cell.mines() + 1).min(MAX_CELL_MINES));
+        }
+        self.mines = mines.len() as u16;
+        self.recount();
+        self.start = true;
+    }
+
+    pub fn mine_positions(&self) -> Vec<(usize, usize)> {
+        self.board
+            .iter()
+            .flatten()
+            .flat_map(|cell| std::iter::repeat_n((cell.x, cell.y), cell.mines() as usize))
+            .collect()
+    }
+
+    pub fn game_id(&self) -> GameId {
+        GameId {
+            rows: self.rows,
+            cols: self.cols,
+            mines: self.mines,
+            seed: self.seed,
+            no_guess: self.no_guess,
+            topology: self.topology,
+            wrap: self.wrap,
+            multi: self.multi,
+            safety: self.safety,
+        }
+    }
+
+    pub fn cells(&self) -> &[Vec<BoardCell>] {
+        &self.board
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> &BoardCell {
+        &self.board[x][y]
+    }
+
+    pub fn started(&self) -> bool {
+        self.start
+    }
+
+    pub fn mines_left(&self) -> i16 {
+        self.mines as i16 - self.flagged_mines
+    }
+
+    // The cells around (x, y), including the cell itself, clipped to the board
+    // or wrapped around it. An edge only wraps if that can't make a cell its
+    // own neighbour twice, and on a hex grid rows only wrap when there's an
+    // even number of them so the shifted rows still line up.
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (rows, cols) = (self.rows as i32, self.cols as i32);
+        let wrap_rows =
+            self.wrap && rows >= 3 && (self.topology == Topology::Square || rows % 2 == 0);
+        let wrap_cols = self.wrap && cols >= 3;
+        self.topology
+            .offsets(x)
+            .iter()
+            .map(move |(dx, dy)| {
+                let (x, y) = (x as i32 + dx, y as i32 + dy);
+                let x = if wrap_rows { x.rem_euclid(rows) } else { x };
+                let y = if wrap_cols { y.rem_euclid(cols) } else { y };
+                (x, y)
+            })
+            .filter(move |(x, y)| 0 <= *x && *x < rows && 0 <= *y && *y < cols)
+            .map(|(x, y)| (x as usize, y as usize))
+    }
+
+    // The neighbours of (x, y) that are only reached by wrapping over an edge.
+    pub fn wrapped_neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbours(x, y)
+            .filter(move |&(x1, y1)| x.abs_diff(x1) > 1 || y.abs_diff(y1) > 1)
+    }
+
+    // The cells kept free of mines when (x, y) is the first cell opened, sorted.
+    // Falls back to a weaker policy when the stronger one leaves too little
+    // room for the mines.
+    fn safe_zone(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let per_cell = if self.multi {
+            MAX_CELL_MINES as usize
+        } else {
+            1
+        };
+        let area = self.rows as usize * self.cols as usize;
+        let mut zone = vec![];
+        for safety in Safety::iter().rev().filter(|safety| *safety <= self.safety) {
+            zone = match safety {
+                Safety::Unprotected => vec![],
+                Safety::SafeCell => vec![(x, y)],
+                Safety::SafeOpening => self.neighbours(x, y).collect(),
+                Safety::ZeroOpening => self
+                    .neighbours(x, y)
+                    .flat_map(|(x1, y1)| self.neighbours(x1, y1))
+                    .collect(),
+            };
+            zone.sort_unstable();
+            zone.dedup();
+            if (area - zone.len()) * per_cell >= self.mines as usize {
+                break;
+            }
+        }
+        zone
+    }
+
+    fn start(&mut self, x: usize, y: usize) {
+        if !self.no_guess {
+            self.populate(x, y, self.seed);
+            return;
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.populate(x, y, rng.gen());
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            match solver::stuck(self, x, y) {
+                Some((sim, known)) => {
+                    if !self.rework(&sim, &known, (x, y), &mut rng) {
+                        self.populate(x, y, rng.gen());
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+
+    // Reworks the cells the solver couldn't decide next to the revealed area so
+    // it can carry on: either all of them become safe or all of them become
+    // mines, with the difference made up in the untouched interior.
+    fn rework(
+        &mut self,
+        sim: &Board,
+        known: &solver::Deductions,
+        (x, y): (usize, usize),
+        rng: &mut ChaCha8Rng,
+    ) -> bool {
+        let opening = self.safe_zone(x, y);
+        let (mut frontier, mut interior, mut rest) = (vec![], vec![], vec![]);
+        for (x1, y1) in
+            (0..self.rows as usize).flat_map(|x| (0..self.cols as usize).map(move |y| (x, y)))
+        {
+            if opening.contains(&(x1, y1)) {
+                continue;
+            }
+            if sim.board[x1][y1].flags() == 0 || known.mines.contains(&(x1, y1)) {
+                rest.push((x1, y1));
+                continue;
+            }
+            if sim
+                .neighbours(x1, y1)
+                .any(|(x2, y2)| sim.board[x2][y2].flags() == 0)
+            {
+                frontier.push((x1, y1));
+            } else {
+                interior.push((x1, y1));
+            }
+        }
+        let (frontier_mines, frontier_safe): (Vec<_>, Vec<_>) = frontier
+            .into_iter()
+            .partition(|&(x1, y1)| self.board[x1][y1].is_mine());
+        let (mut interior_mines, mut interior_safe): (Vec<_>, Vec<_>) = interior
+            .into_iter()
+            .partition(|&(x1, y1)| self.board[x1][y1].is_mine());
+        let mut clear = !frontier_mines.is_empty() && frontier_mines.len() <= interior_safe.len();
+        let mut fill = !frontier_safe.is_empty() && frontier_safe.len() <= interior_mines.len();
+        if !clear && !fill {
+            // Not enough room in the interior, borrow from the part of the board
+            // that was already solved and let the next pass redo it
+            for (x1, y1) in rest {
+                match self.board[x1][y1].is_mine() {
+                    true => interior_mines.push((x1, y1)),
+                    false => interior_safe.push((x1, y1)),
+                }
+            }
+            clear = !frontier_mines.is_empty() && frontier_mines.len() <= interior_safe.len();
+            fill = !frontier_safe.is_empty() && frontier_safe.len() <= interior_mines.len();
+        }
+        let clear = match (clear, fill) {
+            (false, false) => return false,
+            (true, true) => rng.gen(),
+            (clear, _) => clear,
+        };
+        let (from, to) = if clear {
+            let to = interior_safe
+                .choose_multiple(rng, frontier_mines.len())
+                .copied()
+                .collect();
+            (frontier_mines, to)
+        } else {
+            let from = interior_mines
+                .choose_multiple(rng, frontier_safe.len())
+                .copied()
+                .collect();
+            (from, frontier_safe)
+        };
+        for (x1, y1) in from {
+            self.board[x1][y1].set_mines(0);
+        }
+        for (x1, y1) in to {
+            self.board[x1][y1].set_mines(1);
+        }
+        self.recount();
+        true
+    }
+
+    fn recount(&mut self) {
+        for x in 0..self.rows as usize {
+            for y in 0..self.cols as usize {
+                let count = self
+                    .neighbours(x, y)
+                    .map(|(x1, y1)| self.board[x1][y1].mines())
+                    .sum();
+                self.board[x][y].set_value(count);
+            }
+        }
+    }
+
+    fn populate(&mut self, x: usize, y: usize, seed: u64) {
+        for cell in self.board.iter_mut().flatten() {
+            cell.set_mines(0);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        if self.multi {
+            self.populate_multi(x, y, &mut rng);
+            return;
+        }
+        let area = self.rows as usize * self.cols as usize;
+        let places = self
+            .safe_zone(x, y)
+            .into_iter()
+            .map(|(x, y)| x * self.cols as usize + y)
+            .collect::<Vec<usize>>();
+        let places = {
+            let mut temp: Vec<(usize, usize)> = vec![(0, 0)];
+            let (mut start, mut len, mut next) = (area, 0, area);
+            for e in places {
+                if e == next {
+                    len += 1;
+                    next += 1;
+                } else {
+                    if start != area {
+                        temp.push((start, len));
+                    }
+                    start = e;
+                    len = 1;
+                    next = e + 1;
+                }
+            }
+            temp.push((start, len));
+            temp.push((area, 0));
+            temp
+        };
+        let mut pos = (0..(area - places.iter().fold(0, |acc, (_, x)| acc + x))) //Counting is hard
+            .collect::<Vec<usize>>()
+            .choose_multiple(&mut rng, self.mines as _)
+            .copied()
+            .collect::<Vec<usize>>();
+        pos.sort_unstable();
+        let mut delta = 0;
+        let mut i = 0;
+        let pos = pos
+            .iter()
+            .map(|a| {
+                while places[i].0 <= (*a) + delta {
+                    delta += places[i].1;
+                    i += 1;
+                }
+                (*a) + delta
+            })
+            .map(|a| (a / (self.cols as usize), a % (self.cols as usize)))
+            .collect::<Vec<(usize, usize)>>();
+        for (x, y) in pos {
+            self.board[x][y].set_mines(1);
+        }
+        self.recount();
+        self.start = true;
+    }
+
+    // Drops the mines one at a time on random cells that still have room,
+    // keeping clear of the first click.
+    fn populate_multi(&mut self, x: usize, y: usize, rng: &mut ChaCha8Rng) {
+        let opening = self.safe_zone(x, y);
+        let mut places = (0..self.rows as usize)
+            .flat_map(|x| (0..self.cols as usize).map(move |y| (x, y)))
+            .filter(|pos| opening.binary_search(pos).is_err())
+            .collect::<Vec<_>>();
+        for _ in 0..self.mines {
+            if places.is_empty() {
+                break;
+            }
+            let i = rng.gen_range(0..places.len());
+            let (x1, y1) = places[i];
+            let cell = &mut self.board[x1][y1];
+            cell.set_mines(cell.mines() + 1);
+            if cell.mines() == MAX_CELL_MINES {
+                places.swap_remove(i);
+            }
+        }
+        self.recount();
+        self.start = true;
+    }
+
+    // Cells without a mine, all of which have to be opened to win
+    fn safe_cells(&self) -> usize {
+        self.board
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.is_mine())
+            .count()
+    }
+
+    pub fn flag(&mut self, x: usize, y: usize) {
+        if self.game_state != GameState::InProgress {
+            return;
+        }
+        if self.board[x][y].flags() == 0 {
+            self.click(x, y);
+        }
+        let max = if self.multi { MAX_CELL_MINES } else { 1 };
+        self.flagged_mines += self.board[x][y].flag(max) as i16;
+    }
+
+    pub fn click(&mut self, x: usize, y: usize) {
+        if self.game_state != GameState::InProgress {
+            return;
+        }
+        // The layout waits for the first cell actually opened, so that flags
+        // placed before it don't change where the safe zone goes
+        if !self.start && self.board[x][y].flags() == 1 {
+            self.start(x, y);
+        }
+        let mut q = VecDeque::new();
+        let mut set = HashSet::new();
+        // Chord: a revealed number with as many flags around it reveals the rest
+        if self.board[x][y].flags() == 0 {
+            let count = self
+                .neighbours(x, y)
+                .map(|(x1, y1)| self.board[x1][y1].flagged_mines())
+                .sum::<u8>();
+            if count == self.board[x][y].value() {
+                for (x1, y1) in self.neighbours(x, y) {
+                    if self.board[x1][y1].flags() == 1 {
+                        q.push_back((x1, y1));
+                        set.insert((x1, y1));
+                    }
+                }
+            }
+        }
+        if self.board[x][y].flags() == 1 {
+            q.push_back((x, y));
+            set.insert((x, y));
+        }
+        //Maybe optimize in future
+        while let Some((x, y)) = q.pop_front() {
+            //BFS
+            if self.board[x][y].is_mine() {
+                self.game_state = GameState::Lost;
+                self.board[x][y].set_state(4);
+                self.finish();
+                return;
+            }
+            if self.board[x][y].flags() == 1 {
+                self.clicked_cells += 1;
+            }
+            if self.board[x][y].click() {
+                for (x1, y1) in self.neighbours(x, y) {
+                    if self.board[x1][y1].flags() == 1 && !set.contains(&(x1, y1)) {
+                        q.push_back((x1, y1));
+                        set.insert((x1, y1));
+                    }
+                }
+            }
+        }
+        if self.clicked_cells == self.safe_cells() {
+            self.game_state = GameState::Won;
+            self.finish();
+        }
+    }
+
+    // Forget everything the player did, keeping the mine layout
+    fn hide_all(&mut self) {
+        for cell in self.board.iter_mut().flatten() {
+            cell.set_state(1);
+        }
+        self.game_state = GameState::InProgress;
+        self.clicked_cells = 0;
+        self.flagged_mines = 0;
+    }
+
+    // Uncover the whole board once the game is over
+    fn finish(&mut self) {
+        for x in 0..self.rows as _ {
+            for y in 0..self.cols as _ {
+                let cell = &mut self.board[x][y];
+                if !cell.is_mine() {
+                    cell.click();
+                } else if self.game_state == GameState::Won {
+                    cell.set_flagged(cell.mines());
+                } else if cell.flags() != 4 {
+                    cell.set_state(0);
+                }
+            }
+        }
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(9, 9, 10)
+    }
+}
+
+impl From<GameId> for Board {
+    fn from(id: GameId) -> Self {
+        let mut board = Self::with_seed(id.rows, id.cols, id.mines, id.seed);
+        board.topology = id.topology;
+        board.wrap = id.wrap;
+        board.multi = id.multi;
+        // The solver behind no guess boards only knows single mines
+        board.no_guess = id.no_guess && !id.multi;
+        // and needs the first click to open up
+        board.safety = match board.no_guess {
+            true => id.safety.max(Safety::SafeOpening),
+            false => id.safety,
+        };
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safety::Safety;
+    use topology::Topology;
+
+    // A fresh expert board opened in the middle.
+    fn opened() -> Board {
+        let mut board = Board::new(16, 30, 99);
+        board.click(8, 15);
+        board
+    }
+
+    #[test]
+    fn first_click_opens_up() {
+        for _ in 0..50 {
+            let board = opened();
+            assert_eq!(board.mine_positions().len(), 99);
+            assert!(board
+                .neighbours(8, 15)
+                .all(|(x, y)| !board.cell(x, y).is_mine()));
+            assert_eq!(board.cell(8, 15).flags(), 0);
+            assert_eq!(board.game_state, GameState::InProgress);
+        }
+    }
+
+    #[test]
+    fn numbers_count_the_mines_around() {
+        let board = opened();
+        let mines = board.mine_positions();
+        for cell in board
+            .cells()
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.is_mine())
+        {
+            let around = board
+                .neighbours(cell.x, cell.y)
+                .filter(|pos| mines.contains(pos))
+                .count();
+            assert_eq!(cell.value() as usize, around);
+        }
+    }
+
+    #[test]
+    fn flood_fill() {
+        for _ in 0..20 {
+            let board = opened();
+            for cell in board.cells().iter().flatten() {
+                if cell.flags() == 0 && cell.value() == 0 {
+                    assert!(board
+                        .neighbours(cell.x, cell.y)
+                        .all(|(x, y)| board.cell(x, y).flags() == 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn opening_every_safe_cell_wins() {
+        let mut board = opened();
+        let safe = board
+            .cells()
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.is_mine())
+            .map(|cell| (cell.x, cell.y))
+            .collect::<Vec<_>>();
+        for (x, y) in safe {
+            board.click(x, y);
+        }
+        assert_eq!(board.game_state, GameState::Won);
+        let mut board = opened();
+        let (x, y) = board.mine_positions()[0];
+        board.click(x, y);
+        assert_eq!(board.game_state, GameState::Lost);
+        assert_eq!(board.cell(x, y).flags(), 4);
+    }
+
+    #[test]
+    fn flags_cycle() {
+        let mut board = opened();
+        let (x, y) = board.mine_positions()[0];
+        for (flags, left) in [(2, 98), (3, 99), (1, 99)] {
+            board.flag(x, y);
+            assert_eq!(
+                (board.cell(x, y).flags(), board.mines_left()),
+                (flags, left)
+            );
+        }
+    }
+
+    #[test]
+    fn chording() {
+        let mut board = opened();
+        // A number next to a hidden safe cell, chorded once its mines are flagged
+        let (x, y) = board
+            .cells()
+            .iter()
+            .flatten()
+            .find(|cell| {
+                cell.flags() == 0
+                    && cell.value() > 0
+                    && board
+                        .neighbours(cell.x, cell.y)
+                        .any(|(x, y)| board.cell(x, y).flags() == 1 && !board.cell(x, y).is_mine())
+            })
+            .map(|cell| (cell.x, cell.y))
+            .unwrap();
+        let mut wrong = board.clone();
+        for (x1, y1) in board.neighbours(x, y).collect::<Vec<_>>() {
+            if board.cell(x1, y1).is_mine() {
+                board.flag(x1, y1);
+            }
+        }
+        board.click(x, y);
+        assert_ne!(board.game_state, GameState::Lost);
+        assert!(board
+            .neighbours(x, y)
+            .all(|(x1, y1)| matches!(board.cell(x1, y1).flags(), 0 | 2)));
+        // As many flags in the wrong places sets off the mines
+        let around = board.neighbours(x, y).collect::<Vec<_>>();
+        let safe = around
+            .iter()
+            .filter(|&&(x1, y1)| wrong.cell(x1, y1).flags() == 1 && !wrong.cell(x1, y1).is_mine())
+            .copied()
+            .collect::<Vec<_>>();
+        let count = wrong.cell(x, y).value() as usize;
+        if safe.len() >= count {
+            for &(x1, y1) in &safe[..count] {
+                wrong.flag(x1, y1);
+            }
+            wrong.click(x, y);
+            assert_eq!(wrong.game_state, GameState::Lost);
+        }
+    }
+
+    #[test]
+    fn seeded_layouts_repeat() {
+        for seed in [0, 1, 0x5eed, u64::MAX] {
+            let mut first = Board::with_seed(16, 30, 99, seed);
+            let mut second = Board::from(first.game_id());
+            first.click(3, 7);
+            second.click(3, 7);
+            assert_eq!(first.mine_positions(), second.mine_positions());
+        }
+        let mut first = Board::with_seed(16, 30, 99, 1);
+        let mut second = Board::with_seed(16, 30, 99, 2);
+        first.click(3, 7);
+        second.click(3, 7);
+        assert_ne!(first.mine_positions(), second.mine_positions());
+        let id = "12x12x60-abc-h-w-m-z".parse::<GameId>().unwrap();
+        let mut first = Board::from(id);
+        let mut second = Board::from(id);
+        first.click(4, 5);
+        second.click(4, 5);
+        assert_eq!(first.mine_positions(), second.mine_positions());
+        assert_eq!(first.mine_positions().len(), 60);
+        assert_eq!(first.game_state, GameState::InProgress);
+    }
+
+    #[test]
+    fn game_ids() {
+        for id in [
+            "9x9x10-1f2e3d4c",
+            "16x30x99-0",
+            "3x3x0-ffffffffffffffff",
+            "9x9x10-1-n",
+            "20x20x150-ff-n-h-w-m",
+            "9x9x216-1-m",
+            "60x60x9-1-c",
+            "9x9x10-0-u",
+        ] {
+            assert_eq!(id.parse::<GameId>().unwrap().to_string(), id);
+        }
+        for id in [
+            "",
+            "9x9x10",
+            "9x9-1",
+            "9x9x73-1",
+            "2x2x0-1",
+            "9x9x10-g",
+            "9x9x10-1-x",
+            "9x9x10-1-n-n",
+            "9x9x10-1-h-h",
+            "9x9x10-1-w-w",
+            "9x9x10-1-m-m",
+            "9x9x217-1-m",
+            "9x9x10-1-u-z",
+            "61x9x10-1",
+            "300x300x10-1",
+            "60000x60000x1-1",
+        ] {
+            assert_eq!(id.parse::<GameId>(), Err(ParseGameIdError), "{}", id);
+        }
+    }
+
+    #[test]
+    fn no_guess_boards_need_no_guessing() {
+        for seed in 0..8 {
+            let id = format!("16x16x40-{:x}-n", seed).parse::<GameId>().unwrap();
+            let mut board = Board::from(id);
+            board.click(8, 8);
+            assert!(solver::stuck(&board, 8, 8).is_none(), "{}", id);
+            let mut again = Board::from(id);
+            again.click(8, 8);
+            assert_eq!(board.mine_positions(), again.mine_positions());
+        }
+    }
+
+    #[test]
+    fn hex_neighbours() {
+        let mut board = Board::with_seed(5, 5, 5, 0);
+        board.topology = Topology::Hex;
+        assert_eq!(board.neighbours(2, 2).count(), 7);
+        assert_eq!(board.neighbours(0, 0).count(), 3);
+        assert_eq!(board.neighbours(1, 4).count(), 4);
+        // Odd rows are shifted right, so the cells above and below lean right
+        assert!(board.neighbours(1, 1).any(|pos| pos == (0, 2)));
+        assert!(!board.neighbours(1, 1).any(|pos| pos == (0, 0)));
+        // Five rows can't wrap without the shifts going out of step
+        board.wrap = true;
+        assert_eq!(board.neighbours(0, 2).count(), 5);
+        assert!(board.neighbours(0, 0).any(|pos| pos == (1, 4)));
+    }
+
+    #[test]
+    fn wrapped_neighbours() {
+        let mut board = Board::with_seed(5, 5, 5, 0);
+        assert_eq!(board.neighbours(0, 0).count(), 4);
+        board.wrap = true;
+        assert_eq!(board.neighbours(0, 0).count(), 9);
+        assert!(board.neighbours(0, 0).any(|pos| pos == (4, 4)));
+        assert_eq!(board.wrapped_neighbours(0, 0).count(), 5);
+        assert_eq!(board.wrapped_neighbours(2, 2).count(), 0);
+        // Too narrow to wrap without a cell being its own neighbour twice
+        let mut board = Board::with_seed(2, 5, 1, 0);
+        board.wrap = true;
+        assert_eq!(board.neighbours(0, 0).count(), 6);
+    }
+
+    #[test]
+    fn multi_mine_flags() {
+        let mut board = Board::with_seed(3, 3, 0, 0);
+        board.multi = true;
+        board.place_mines(&[(0, 0), (0, 0), (2, 2)]);
+        assert_eq!(board.cell(0, 0).mines(), 2);
+        assert_eq!(board.cell(1, 1).value(), 3);
+        assert_eq!(board.mines_left(), 3);
+        for (flags, left) in [(2, 2), (2, 1), (2, 0), (3, 3), (1, 3)] {
+            board.flag(0, 0);
+            assert_eq!(
+                (board.cell(0, 0).flags(), board.mines_left()),
+                (flags, left)
+            );
+        }
+        board.flag(0, 0);
+        board.flag(0, 0);
+        board.flag(2, 2);
+        board.click(1, 1);
+        assert_eq!(board.game_state, GameState::InProgress);
+        // Two flags on one cell count for both of its mines when chording
+        board.click(1, 1);
+        assert_eq!(board.game_state, GameState::Won);
+    }
+
+    #[test]
+    fn safe_zones_stay_clear() {
+        for safety in Safety::iter().filter(|safety| *safety != Safety::Unprotected) {
+            for seed in 0..20 {
+                let mut board = Board::with_seed(9, 9, 40, seed);
+                board.safety = safety;
+                board.click(4, 4);
+                let mines = board.mine_positions();
+                assert_eq!(mines.len(), 40);
+                assert!(board.safe_zone(4, 4).iter().all(|pos| !mines.contains(pos)));
+            }
+        }
+        // Too many mines for the whole opening, the clicked cell still stays clear
+        let mut board = Board::with_seed(5, 5, 20, 7);
+        board.click(2, 2);
+        assert_eq!(board.safe_zone(2, 2), vec![(2, 2)]);
+        assert!(!board.cell(2, 2).is_mine());
+    }
+
+    #[test]
+    fn flags_before_the_first_click() {
+        let mut board = Board::with_seed(9, 9, 10, 3);
+        board.flag(0, 0);
+        assert!(!board.started());
+        assert_eq!(board.mines_left(), 9);
+        board.click(4, 4);
+        assert!(board.started());
+        assert!(board
+            .neighbours(4, 4)
+            .all(|(x, y)| !board.cell(x, y).is_mine()));
+    }
+}
@@ -0,0 +1,136 @@
+use super::topology::Topology;
+use super::Board;
+
+use std::fmt;
+
+// The layout at the start of a Minesweeper Arbiter video (.avf): a version
+// byte, four bytes we don't need, the level (3 beginner, 4 intermediate,
+// 5 expert, 6 custom), for custom boards the width and height minus one and a
+// big-endian mine count, then every mine as its 1-based row and column. The
+// player info and mouse events that follow aren't read.
+
+const BEGINNER: u8 = 3;
+const INTERMEDIATE: u8 = 4;
+const EXPERT: u8 = 5;
+const CUSTOM: u8 = 6;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseAvfError;
+
+impl fmt::Display for ParseAvfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected an Arbiter video starting with a board layout")
+    }
+}
+
+// The board with its mines laid out and nothing opened yet.
+pub fn read(bytes: &[u8]) -> Result<Board, ParseAvfError> {
+    let mut bytes = bytes.iter().copied();
+    let mut next = || bytes.next().ok_or(ParseAvfError);
+    for _ in 0..5 {
+        next()?;
+    }
+    let (rows, cols, mines) = match next()? {
+        BEGINNER => (8, 8, 10),
+        INTERMEDIATE => (16, 16, 40),
+        EXPERT => (16, 30, 99),
+        CUSTOM => {
+            let cols = next()? as u16 + 1;
+            let rows = next()? as u16 + 1;
+            (rows, cols, u16::from_be_bytes([next()?, next()?]))
+        }
+        _ => return Err(ParseAvfError),
+    };
+    let mut positions = Vec::with_capacity(mines as usize);
+    for _ in 0..mines {
+        let (row, col) = (next()? as usize, next()? as usize);
+        if !(1..=rows as usize).contains(&row) || !(1..=cols as usize).contains(&col) {
+            return Err(ParseAvfError);
+        }
+        positions.push((row - 1, col - 1));
+    }
+    positions.sort_unstable();
+    positions.dedup();
+    if positions.len() != mines as usize || mines as usize >= rows as usize * cols as usize {
+        return Err(ParseAvfError);
+    }
+    Ok(Board::with_mines(rows, cols, &positions))
+}
+
+// Just the layout header, None before the mines are laid out and for boards
+// Arbiter can't play.
+pub fn write(board: &Board) -> Option<Vec<u8>> {
+    if !board.started()
+        || board.multi
+        || board.wrap
+        || board.topology != Topology::Square
+        || board.rows > 255
+        || board.cols > 255
+    {
+        return None;
+    }
+    let mut bytes = vec![0; 5];
+    match (board.rows, board.cols, board.mines) {
+        (8, 8, 10) => bytes.push(BEGINNER),
+        (16, 16, 40) => bytes.push(INTERMEDIATE),
+        (16, 30, 99) => bytes.push(EXPERT),
+        (rows, cols, mines) => {
+            bytes.extend([CUSTOM, (cols - 1) as u8, (rows - 1) as u8]);
+            bytes.extend(mines.to_be_bytes());
+        }
+    }
+    for (x, y) in board.mine_positions() {
+        bytes.extend([x as u8 + 1, y as u8 + 1]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The start of an expert Arbiter video, with the player info and mouse
+    // events that follow the layout.
+    const EXPERT_VIDEO: &[u8] = include_bytes!("testdata/expert.avf");
+
+    #[test]
+    fn reads_arbiter_videos() {
+        let board = read(EXPERT_VIDEO).unwrap();
+        assert_eq!((board.rows, board.cols, board.mines), (16, 30, 99));
+        let mines = board.mine_positions();
+        assert_eq!(mines.len(), 99);
+        assert!(mines.contains(&(0, 0)) && mines.contains(&(0, 2)));
+        assert!(!board.cell(0, 3).is_mine());
+    }
+
+    #[test]
+    fn round_trip() {
+        let board = read(EXPERT_VIDEO).unwrap();
+        let bytes = write(&board).unwrap();
+        assert_eq!(bytes[5], EXPERT);
+        assert_eq!(
+            read(&bytes).unwrap().mine_positions(),
+            board.mine_positions()
+        );
+        let custom = Board::with_mines(9, 12, &[(0, 0), (8, 11), (4, 5)]);
+        let bytes = write(&custom).unwrap();
+        assert_eq!(bytes[5..10], [CUSTOM, 11, 8, 0, 3]);
+        assert_eq!(
+            read(&bytes).unwrap().mine_positions(),
+            custom.mine_positions()
+        );
+    }
+
+    #[test]
+    fn rejects_bad_videos() {
+        for bytes in [
+            &[][..],
+            &[0, 0, 0, 0, 0, 7],
+            &[0, 0, 0, 0, 0, CUSTOM, 1, 1, 0, 1, 3, 1],
+            &[0, 0, 0, 0, 0, CUSTOM, 1, 1, 0, 2, 1, 1, 1, 1],
+            &EXPERT_VIDEO[..100],
+        ] {
+            assert_eq!(read(bytes), Err(ParseAvfError));
+        }
+    }
+}
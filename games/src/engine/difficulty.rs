@@ -0,0 +1,32 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+// The usual board presets, rows by columns with their mine count.
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Extreme,
+    Custom,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Difficulty {
+    pub const fn value(&self) -> (u16, u16, u16) {
+        match self {
+            Difficulty::Easy => (9, 9, 10),
+            Difficulty::Medium => (16, 16, 40),
+            Difficulty::Hard => (16, 30, 99),
+            Difficulty::Extreme => (24, 30, 180),
+            Difficulty::Custom => (0, 0, 0),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use super::{Board, GameState};
+
+use serde::{Deserialize, Serialize};
+
+// A single player action on the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Move {
+    Reveal(usize, usize),
+    Chord(usize, usize),
+    Flag(usize, usize),
+}
+
+impl Move {
+    // What a click on (x, y) does: open a hidden cell or chord around a number.
+    pub fn click(board: &Board, x: usize, y: usize) -> Self {
+        match board.cell(x, y).flags() {
+            0 => Move::Chord(x, y),
+            _ => Move::Reveal(x, y),
+        }
+    }
+
+    pub fn pos(&self) -> (usize, usize) {
+        match *self {
+            Move::Reveal(x, y) | Move::Chord(x, y) | Move::Flag(x, y) => (x, y),
+        }
+    }
+}
+
+impl Board {
+    pub fn play(&mut self, mv: Move) {
+        match mv {
+            Move::Reveal(x, y) | Move::Chord(x, y) => self.click(x, y),
+            Move::Flag(x, y) => self.flag(x, y),
+        }
+    }
+}
+
+// Undo/redo stacks for a board. Undoing restores the board as it was before
+// the move, redoing plays the move again, which gives the same result since
+// the layout only depends on the seed and the first click.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct History {
+    undo: Vec<(Move, Board)>,
+    redo: Vec<Move>,
+    // Lets a lost game be taken back
+    pub practice: bool,
+    // Set once anything was undone, such games shouldn't count for the records
+    pub used: bool,
+}
+
+impl History {
+    pub fn new(practice: bool) -> Self {
+        History {
+            practice,
+            ..Default::default()
+        }
+    }
+
+    // Moves that don't change anything aren't recorded.
+    pub fn play(&mut self, board: &mut Board, mv: Move) {
+        if board.game_state != GameState::InProgress {
+            return;
+        }
+        let before = board.clone();
+        board.play(mv);
+        if *board != before {
+            self.undo.push((mv, before));
+            self.redo.clear();
+        }
+    }
+
+    pub fn can_undo(&self, board: &Board) -> bool {
+        !self.undo.is_empty()
+            && match board.game_state {
+                GameState::InProgress => true,
+                GameState::Lost => self.practice,
+                GameState::Won => false,
+            }
+    }
+
+    pub fn can_redo(&self, board: &Board) -> bool {
+        !self.redo.is_empty() && board.game_state == GameState::InProgress
+    }
+
+    pub fn undo(&mut self, board: &mut Board) {
+        if !self.can_undo(board) {
+            return;
+        }
+        if let Some((mv, before)) = self.undo.pop() {
+            // Flag mode is a setting, not part of the move
+            let flag = board.flag;
+            *board = before;
+            board.flag = flag;
+            self.redo.push(mv);
+            self.used = true;
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) {
+        if !self.can_redo(board) {
+            return;
+        }
+        if let Some(mv) = self.redo.pop() {
+            let before = board.clone();
+            board.play(mv);
+            self.undo.push((mv, before));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mine(board: &Board) -> (usize, usize) {
+        let cell = board
+            .cells()
+            .iter()
+            .flatten()
+            .find(|cell| cell.is_mine())
+            .unwrap();
+        (cell.x, cell.y)
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut board = Board::with_seed(9, 9, 10, 3);
+        let mut history = History::new(false);
+        let fresh = board.clone();
+        history.play(&mut board, Move::Reveal(4, 4));
+        let (x, y) = mine(&board);
+        history.play(&mut board, Move::Flag(x, y));
+        let played = board.clone();
+        assert!(history.can_undo(&board));
+        assert!(!history.can_redo(&board));
+        history.undo(&mut board);
+        history.undo(&mut board);
+        assert_eq!(board, fresh);
+        assert!(history.used);
+        assert!(!history.can_undo(&board));
+        history.redo(&mut board);
+        history.redo(&mut board);
+        assert_eq!(board, played);
+        // A new move drops whatever was left to redo
+        history.undo(&mut board);
+        history.play(&mut board, Move::Reveal(0, 0));
+        assert!(!history.can_redo(&board));
+    }
+
+    #[test]
+    fn losses_only_undo_in_practice() {
+        for practice in [false, true] {
+            let mut board = Board::with_seed(9, 9, 10, 3);
+            let mut history = History::new(practice);
+            history.play(&mut board, Move::Reveal(4, 4));
+            let before = board.clone();
+            let (x, y) = mine(&board);
+            history.play(&mut board, Move::Reveal(x, y));
+            assert_eq!(board.game_state, GameState::Lost);
+            assert_eq!(history.can_undo(&board), practice);
+            history.undo(&mut board);
+            if practice {
+                assert_eq!(board, before);
+            } else {
+                assert_eq!(board.game_state, GameState::Lost);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::history::Move;
+use super::replay::{Action, Replay};
+use super::{Board, GameState};
+
+use std::collections::VecDeque;
+
+// The usual competitive numbers for a finished game, worked out from its replay.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Metrics {
+    // Minimum number of clicks needed to clear the board
+    pub bbbv: u32,
+    // How much of that the player got done
+    pub solved: u32,
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+    pub ms: u64,
+}
+
+impl Metrics {
+    pub fn new(replay: &Replay) -> Self {
+        let mut metrics = Metrics {
+            ms: replay.duration(),
+            ..Default::default()
+        };
+        let mut player = replay.player();
+        let mut before = player.board.clone();
+        loop {
+            let board = player.board.clone();
+            match player.step() {
+                Some((_, Action::Play(mv))) => match mv {
+                    Move::Reveal(..) => metrics.left += 1,
+                    Move::Flag(..) => metrics.right += 1,
+                    Move::Chord(..) => metrics.chord += 1,
+                },
+                Some(_) => (),
+                None => break,
+            }
+            before = board;
+        }
+        // A lost board is uncovered completely, what the player had opened is
+        // the board as it was before the last move
+        let progress = match player.board.game_state {
+            GameState::Lost => &before,
+            _ => &player.board,
+        };
+        (metrics.bbbv, metrics.solved) = bbbv(&player.board, progress);
+        metrics
+    }
+
+    pub fn clicks(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+
+    pub fn bbbv_per_second(&self) -> f64 {
+        self.solved as f64 * 1000.0 / self.ms.max(1) as f64
+    }
+
+    // Efficiency: 3BV per click
+    pub fn ioe(&self) -> f64 {
+        match self.clicks() {
+            0 => 0.0,
+            clicks => self.solved as f64 / clicks as f64,
+        }
+    }
+
+    pub fn completion(&self) -> f64 {
+        match self.bbbv {
+            0 => 0.0,
+            bbbv => self.solved as f64 / bbbv as f64,
+        }
+    }
+}
+
+// 3BV of the layout on `board`: one for every opening, plus one for every
+// number not bordering an opening. Also returns how many of those are
+// uncovered on `progress`.
+pub fn bbbv(board: &Board, progress: &Board) -> (u32, u32) {
+    let revealed = |x: usize, y: usize| progress.cell(x, y).flags() == 0;
+    let (rows, cols) = (board.rows as usize, board.cols as usize);
+    let mut covered = vec![vec![false; cols]; rows];
+    let (mut total, mut solved) = (0, 0);
+    for cell in board.cells().iter().flatten() {
+        if cell.is_mine() || cell.value() != 0 || covered[cell.x][cell.y] {
+            continue;
+        }
+        // Opening any cell of an opening opens all of it
+        let mut opened = false;
+        let mut q = VecDeque::from([(cell.x, cell.y)]);
+        covered[cell.x][cell.y] = true;
+        while let Some((x, y)) = q.pop_front() {
+            opened |= revealed(x, y);
+            for (nx, ny) in board.neighbours(x, y) {
+                if covered[nx][ny] {
+                    continue;
+                }
+                covered[nx][ny] = true;
+                let next = board.cell(nx, ny);
+                if !next.is_mine() && next.value() == 0 {
+                    q.push_back((nx, ny));
+                }
+            }
+        }
+        total += 1;
+        solved += opened as u32;
+    }
+    for cell in board.cells().iter().flatten() {
+        if !cell.is_mine() && !covered[cell.x][cell.y] {
+            total += 1;
+            solved += revealed(cell.x, cell.y) as u32;
+        }
+    }
+    (total, solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::history::History;
+
+    fn played(board: &mut Board, moves: &[Move]) -> Replay {
+        let mut history = History::new(false);
+        let mut replay = Replay::new(board.game_id());
+        replay.mines = board.mine_positions();
+        for (i, &mv) in moves.iter().enumerate() {
+            history.play(board, mv);
+            replay.record(1000 * (i as u64 + 1), Action::Play(mv), board);
+        }
+        replay
+    }
+
+    #[test]
+    fn openings_and_lone_numbers() {
+        let board = Board::with_mines(3, 3, &[(0, 0)]);
+        assert_eq!(bbbv(&board, &board), (1, 0));
+        // Two openings either side of the mine, and a number above and below
+        let board = Board::with_mines(3, 5, &[(1, 2)]);
+        assert_eq!(bbbv(&board, &board), (4, 0));
+    }
+
+    #[test]
+    fn metrics_of_a_game() {
+        let mut board = Board::with_mines(3, 5, &[(1, 2)]);
+        let replay = played(
+            &mut board,
+            &[Move::Reveal(0, 0), Move::Flag(1, 2), Move::Reveal(0, 2)],
+        );
+        let metrics = Metrics::new(&replay);
+        assert_eq!((metrics.bbbv, metrics.solved), (4, 2));
+        assert_eq!((metrics.left, metrics.right, metrics.chord), (2, 1, 0));
+        assert_eq!(metrics.ms, 3000);
+        assert_eq!(metrics.completion(), 0.5);
+        assert!((metrics.ioe() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((metrics.bbbv_per_second() - 2.0 / 3.0).abs() < 1e-9);
+
+        // Losing counts what was open before the mine went off
+        let mut board = Board::with_mines(3, 5, &[(1, 2)]);
+        let replay = played(&mut board, &[Move::Reveal(0, 4), Move::Reveal(1, 2)]);
+        assert_eq!(board.game_state, GameState::Lost);
+        let metrics = Metrics::new(&replay);
+        assert_eq!((metrics.bbbv, metrics.solved), (4, 1));
+    }
+}
@@ -0,0 +1,195 @@
+use super::solver::{self, Solutions};
+use super::Board;
+
+// Chance of each cell holding a mine given everything revealed so far, None
+// for cells already revealed. Every arrangement consistent with the numbers and
+// the mines left is equally likely, so an arrangement counts once for each way
+// of spreading its leftover mines over the cells no number touches.
+pub type Probabilities = Vec<Vec<Option<f64>>>;
+
+// Solution counts get divided by this to keep products of them in range.
+fn scale(solutions: &Solutions) -> f64 {
+    solutions
+        .by_mines
+        .iter()
+        .map(|(count, _)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64
+}
+
+// Mine count distribution of a component, scaled so the largest entry is 1.
+fn weights(solutions: &Solutions) -> Vec<f64> {
+    let scale = scale(solutions);
+    solutions
+        .by_mines
+        .iter()
+        .map(|(count, _)| *count as f64 / scale)
+        .collect()
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+// ln of n choose k for every k, the interior can easily be too big for the
+// binomials themselves to fit in a f64.
+fn ln_binomials(n: usize) -> Vec<f64> {
+    let ln_fact = (0..=n)
+        .scan(0.0, |acc, i| {
+            if i > 0 {
+                *acc += (i as f64).ln();
+            }
+            Some(*acc)
+        })
+        .collect::<Vec<f64>>();
+    (0..=n)
+        .map(|k| ln_fact[n] - ln_fact[k] - ln_fact[n - k])
+        .collect()
+}
+
+// Returns None when some frontier component is too big to enumerate, or for
+// multi-mine boards which the solver doesn't handle.
+pub fn probabilities(board: &Board) -> Option<Probabilities> {
+    if board.multi {
+        return None;
+    }
+    let known = solver::deduce(board);
+    let solved = solver::solve_components(board, &known)
+        .into_iter()
+        .map(|(component, solutions)| solutions.map(|s| (component, s)))
+        .collect::<Option<Vec<_>>>()?;
+    let left = (board.mines as usize).checked_sub(known.mines.len())?;
+    let frontier = solved.iter().map(|(c, _)| c.cells.len()).sum::<usize>();
+    let interior = solver::unknown_cells(board, &known).len() - frontier;
+
+    // Weight of leaving `m` mines for the interior, relative to the largest one
+    let ln_binomials = ln_binomials(interior);
+    let max_ln = (left.saturating_sub(frontier)..=left.min(interior))
+        .map(|m| ln_binomials[m])
+        .fold(f64::NEG_INFINITY, f64::max);
+    if max_ln == f64::NEG_INFINITY {
+        return None;
+    }
+    let spread = |frontier_mines: usize| -> f64 {
+        match left.checked_sub(frontier_mines) {
+            Some(m) if m <= interior => (ln_binomials[m] - max_ln).exp(),
+            _ => 0.0,
+        }
+    };
+
+    let weights = solved.iter().map(|(_, s)| weights(s)).collect::<Vec<_>>();
+    let all = weights.iter().fold(vec![1.0], |acc, w| convolve(&acc, w));
+    let total = all
+        .iter()
+        .enumerate()
+        .map(|(k, w)| w * spread(k))
+        .sum::<f64>();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut out = board
+        .cells()
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| (cell.flags() != 0).then_some(0.0))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Probabilities>();
+    if interior > 0 {
+        let mines = all
+            .iter()
+            .enumerate()
+            .map(|(k, w)| w * spread(k) * left.saturating_sub(k) as f64)
+            .sum::<f64>();
+        let p = mines / total / interior as f64;
+        for row in out.iter_mut() {
+            for p_cell in row.iter_mut().flatten() {
+                *p_cell = p;
+            }
+        }
+    }
+    for &(x, y) in &known.safe {
+        out[x][y] = Some(0.0);
+    }
+    for &(x, y) in &known.mines {
+        out[x][y] = Some(1.0);
+    }
+    for (j, (component, solutions)) in solved.iter().enumerate() {
+        let others = weights
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != j)
+            .fold(vec![1.0], |acc, (_, w)| convolve(&acc, w));
+        let scale = scale(solutions);
+        let mut mines = vec![0.0; component.cells.len()];
+        for (k, (_, cells)) in solutions.by_mines.iter().enumerate() {
+            let rest = others
+                .iter()
+                .enumerate()
+                .map(|(k2, w)| w * spread(k + k2))
+                .sum::<f64>();
+            for (m, c) in mines.iter_mut().zip(cells) {
+                *m += *c as f64 / scale * rest;
+            }
+        }
+        for (&(x, y), m) in component.cells.iter().zip(mines) {
+            out[x][y] = Some(m / total);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::safety::Safety;
+
+    #[test]
+    fn matches_brute_force() {
+        for board in solver::positions() {
+            let layouts = solver::layouts(&board);
+            let probabilities = probabilities(&board).unwrap();
+            for cell in board.cells().iter().flatten() {
+                let p = probabilities[cell.x][cell.y];
+                if cell.flags() == 0 {
+                    assert_eq!(p, None);
+                    continue;
+                }
+                let mines = layouts
+                    .iter()
+                    .filter(|mines| mines.contains(&(cell.x, cell.y)))
+                    .count();
+                let expected = mines as f64 / layouts.len() as f64;
+                assert!((p.unwrap() - expected).abs() < 1e-9, "{:?}", board);
+            }
+        }
+    }
+
+    #[test]
+    fn big_interiors() {
+        // 3000 mines over 3599 cells, far past what the binomials fit in
+        let mut board = Board::with_seed(60, 60, 3000, 0);
+        board.safety = Safety::SafeCell;
+        board.click(30, 30);
+        let probabilities = probabilities(&board).unwrap();
+        let total = probabilities.iter().flatten().flatten().sum::<f64>();
+        assert!((total - board.mines as f64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn none_for_multi_mine_boards() {
+        let mut board = Board::with_seed(9, 9, 10, 0);
+        board.multi = true;
+        board.click(4, 4);
+        assert_eq!(probabilities(&board), None);
+    }
+}
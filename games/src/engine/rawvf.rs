@@ -0,0 +1,238 @@
+use super::history::Move;
+use super::replay::{Action, Replay};
+use super::safety::Safety;
+use super::topology::Topology;
+use super::{Board, GameId, GameState};
+
+use std::fmt;
+
+// The RAW Minesweeper video format, the plain-text format community tools
+// convert their videos to and from. A "Key: value" header, then the layout
+// after "Board:" with `*` for mines and `0` for safe cells, then one event per
+// line after "Events:", like `1.23 lr 24 40 (2 3)`: seconds, the event, the
+// mouse position in pixels and the 1-based column and row of the cell under it.
+//
+// Only the events that change the board are kept: left releases (`lr`) reveal
+// or chord, right presses (`rc`) flag and middle releases (`mr`) chord.
+// Undos and redos have no equivalent and are left out when writing.
+
+// Pixels per cell in the mouse positions
+const CELL: usize = 16;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseRawError;
+
+impl fmt::Display for ParseRawError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected a RAW video with Width, Height, Board and Events"
+        )
+    }
+}
+
+fn level(id: &GameId) -> &'static str {
+    match (id.rows, id.cols, id.mines) {
+        (8, 8, 10) => "Beginner",
+        (16, 16, 40) => "Intermediate",
+        (16, 30, 99) => "Expert",
+        _ => "Custom",
+    }
+}
+
+// None for replays that never got past the first click, and for the variants
+// no other program knows about.
+pub fn write(replay: &Replay) -> Option<String> {
+    let id = &replay.game_id;
+    if replay.mines.is_empty() || id.topology != Topology::Square || id.wrap || id.multi {
+        return None;
+    }
+    let mut lines = vec![
+        "RawVF_Version: Rev2".to_string(),
+        format!("Program: {}", env!("CARGO_PKG_NAME")),
+        format!("Level: {}", level(id)),
+        format!("Width: {}", id.cols),
+        format!("Height: {}", id.rows),
+        format!("Mines: {}", replay.mines.len()),
+        "Marks: On".to_string(),
+        format!("Time: {:.3}", replay.duration() as f64 / 1000.0),
+        "Board:".to_string(),
+    ];
+    let mut board = vec![vec!['0'; id.cols as usize]; id.rows as usize];
+    for &(x, y) in &replay.mines {
+        board[x][y] = '*';
+    }
+    lines.extend(board.into_iter().map(String::from_iter));
+    lines.push("Events:".to_string());
+    lines.push("0.000 start".to_string());
+    for &(ms, action) in &replay.actions {
+        let (press, release, (x, y)) = match action {
+            Action::Play(Move::Reveal(x, y)) => ("lc", "lr", (x, y)),
+            Action::Play(Move::Chord(x, y)) => ("mc", "mr", (x, y)),
+            Action::Play(Move::Flag(x, y)) => ("rc", "rr", (x, y)),
+            Action::Undo | Action::Redo => continue,
+        };
+        for event in [press, release] {
+            lines.push(format!(
+                "{:.3} {} {} {} ({} {})",
+                ms as f64 / 1000.0,
+                event,
+                y * CELL + CELL / 2,
+                x * CELL + CELL / 2,
+                y + 1,
+                x + 1
+            ));
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+// The cell an event happened on, from the "(column row)" at the end.
+fn event_cell(line: &str, rows: u16, cols: u16) -> Result<(usize, usize), ParseRawError> {
+    let (_, rest) = line.split_once('(').ok_or(ParseRawError)?;
+    let (cell, _) = rest.split_once(')').ok_or(ParseRawError)?;
+    let mut parts = cell.split_whitespace().map(str::parse::<usize>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(col)), Some(Ok(row)))
+            if (1..=rows as usize).contains(&row) && (1..=cols as usize).contains(&col) =>
+        {
+            Ok((row - 1, col - 1))
+        }
+        _ => Err(ParseRawError),
+    }
+}
+
+pub fn read(text: &str) -> Result<Replay, ParseRawError> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let (mut rows, mut cols) = (None, None);
+    for line in lines.by_ref() {
+        if line == "Board:" {
+            break;
+        }
+        match line.split_once(':').map(|(key, value)| (key, value.trim())) {
+            Some(("Width", value)) => cols = value.parse::<u16>().ok(),
+            Some(("Height", value)) => rows = value.parse::<u16>().ok(),
+            _ => (),
+        }
+    }
+    let (rows, cols) = match (rows, cols) {
+        (Some(rows), Some(cols)) if rows > 0 && cols > 0 => (rows, cols),
+        _ => return Err(ParseRawError),
+    };
+    let mut mines = vec![];
+    for x in 0..rows as usize {
+        let line = lines.next().ok_or(ParseRawError)?;
+        if line.chars().count() != cols as usize {
+            return Err(ParseRawError);
+        }
+        for (y, symbol) in line.chars().enumerate() {
+            match symbol {
+                '*' => mines.push((x, y)),
+                '0' => (),
+                _ => return Err(ParseRawError),
+            }
+        }
+    }
+    if lines.next() != Some("Events:") {
+        return Err(ParseRawError);
+    }
+    let mut replay = Replay::new(GameId {
+        rows,
+        cols,
+        mines: mines.len() as u16,
+        seed: 0,
+        no_guess: false,
+        topology: Topology::Square,
+        wrap: false,
+        multi: false,
+        safety: Safety::default(),
+    });
+    replay.mines = mines;
+    // Left releases only tell a reveal from a chord by what's under them
+    let mut board = Board::from(replay.game_id);
+    board.place_mines(&replay.mines);
+    for line in lines {
+        let mut parts = line.split_whitespace();
+        let (seconds, event) = match (parts.next().map(str::parse::<f64>), parts.next()) {
+            (Some(Ok(seconds)), Some(event)) => (seconds, event),
+            _ => continue,
+        };
+        let mv = match event {
+            "lr" => {
+                let (x, y) = event_cell(line, rows, cols)?;
+                Move::click(&board, x, y)
+            }
+            "rc" => {
+                let (x, y) = event_cell(line, rows, cols)?;
+                Move::Flag(x, y)
+            }
+            "mr" => {
+                let (x, y) = event_cell(line, rows, cols)?;
+                Move::Chord(x, y)
+            }
+            _ => continue,
+        };
+        if board.game_state == GameState::InProgress {
+            board.play(mv);
+        }
+        // Some programs log the clicks made before the clock starts with
+        // negative times
+        let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+        replay.actions.push((ms, Action::Play(mv)));
+    }
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A beginner game laid out the way Minesweeper Arbiter exports its
+    // videos: a longer header, mouse moves, presses logged before the clock
+    // starts and a closing "won".
+    const ARBITER: &str = include_str!("testdata/beginner.rawvf");
+
+    #[test]
+    fn reads_arbiter_videos() {
+        let replay = read(ARBITER).unwrap();
+        assert_eq!(
+            (
+                replay.game_id.rows,
+                replay.game_id.cols,
+                replay.game_id.mines
+            ),
+            (8, 8, 10)
+        );
+        assert!(replay.mines.contains(&(0, 6)) && replay.mines.contains(&(7, 7)));
+        assert_eq!(
+            replay.actions.first(),
+            Some(&(0, Action::Play(Move::Reveal(7, 4))))
+        );
+        assert_eq!(replay.duration(), 9420);
+        let mut player = replay.player();
+        player.advance_to(replay.duration());
+        assert!(player.finished());
+        assert_eq!(player.board.game_state, GameState::Won);
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = read(ARBITER).unwrap();
+        let again = read(&write(&replay).unwrap()).unwrap();
+        assert_eq!(again.mines, replay.mines);
+        assert_eq!(again.actions, replay.actions);
+    }
+
+    #[test]
+    fn rejects_bad_videos() {
+        for text in [
+            "",
+            "Width: 2\nHeight: 2\nBoard:\n*0\n00",
+            "Width: 2\nHeight: 2\nBoard:\n*0\n0\nEvents:",
+            "Width: 2\nHeight: 2\nBoard:\n*0\n0x\nEvents:",
+            "Width: 2\nHeight: 2\nBoard:\n*0\n00\nEvents:\n0.00 lr 8 8 (3 1)",
+        ] {
+            assert_eq!(read(text), Err(ParseRawError));
+        }
+    }
+}
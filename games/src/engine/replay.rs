@@ -0,0 +1,148 @@
+use super::history::{History, Move};
+use super::{Board, GameId};
+
+use serde::{Deserialize, Serialize};
+
+// Something the player did, as recorded for a replay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Play(Move),
+    Undo,
+    Redo,
+}
+
+// A recorded game: the mine layout plus every action with the milliseconds
+// elapsed since the clock started when it happened.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub game_id: GameId,
+    pub mines: Vec<(usize, usize)>,
+    pub actions: Vec<(u64, Action)>,
+}
+
+impl Replay {
+    pub fn new(game_id: GameId) -> Self {
+        Replay {
+            game_id,
+            mines: vec![],
+            actions: vec![],
+        }
+    }
+
+    // The layout is only known once the first click generated it, and may
+    // change if that click gets undone and another cell is opened instead.
+    pub fn record(&mut self, ms: u64, action: Action, board: &Board) {
+        self.actions.push((ms, action));
+        if board.started() {
+            self.mines = board.mine_positions();
+        }
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.actions.last().map_or(0, |(ms, _)| *ms)
+    }
+
+    pub fn player(&self) -> Player {
+        Player::new(self.clone())
+    }
+}
+
+// Steps through a replay on a fresh copy of its board.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Player {
+    replay: Replay,
+    pub board: Board,
+    history: History,
+    next: usize,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let id = replay.game_id;
+        let mut board = Board::from(id);
+        board.place_mines(&replay.mines);
+        Player {
+            replay,
+            board,
+            // Whatever got undone in the game can be undone again here
+            history: History::new(true),
+            next: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.replay.actions.len()
+    }
+
+    pub fn last_action(&self) -> Option<(u64, Action)> {
+        self.next.checked_sub(1).map(|i| self.replay.actions[i])
+    }
+
+    pub fn next_time(&self) -> Option<u64> {
+        self.replay.actions.get(self.next).map(|(ms, _)| *ms)
+    }
+
+    pub fn step(&mut self) -> Option<(u64, Action)> {
+        let (ms, action) = *self.replay.actions.get(self.next)?;
+        match action {
+            Action::Play(mv) => self.history.play(&mut self.board, mv),
+            Action::Undo => self.history.undo(&mut self.board),
+            Action::Redo => self.history.redo(&mut self.board),
+        }
+        self.next += 1;
+        Some((ms, action))
+    }
+
+    // Plays every action that happened up to `ms`.
+    pub fn advance_to(&mut self, ms: u64) {
+        while self.next_time().is_some_and(|next| next <= ms) {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_end_on_the_same_board() {
+        let mut board = Board::with_seed(9, 9, 10, 5);
+        let mut history = History::new(true);
+        let mut replay = Replay::new(board.game_id());
+        let mut ms = 0;
+        let mut act = |board: &mut Board, history: &mut History, action| {
+            match action {
+                Action::Play(mv) => history.play(board, mv),
+                Action::Undo => history.undo(board),
+                Action::Redo => history.redo(board),
+            }
+            ms += 250;
+            replay.record(ms, action, board);
+        };
+        act(&mut board, &mut history, Action::Play(Move::Reveal(4, 4)));
+        let (x, y) = board.mine_positions()[0];
+        act(&mut board, &mut history, Action::Play(Move::Flag(x, y)));
+        act(&mut board, &mut history, Action::Undo);
+        act(&mut board, &mut history, Action::Redo);
+        act(&mut board, &mut history, Action::Play(Move::Reveal(0, 8)));
+        assert_eq!(replay.duration(), 1250);
+        assert_eq!(replay.mines, board.mine_positions());
+
+        let mut player = replay.player();
+        player.advance_to(600);
+        assert_eq!(
+            player.last_action(),
+            Some((500, Action::Play(Move::Flag(x, y))))
+        );
+        assert_eq!(player.next_time(), Some(750));
+        player.advance_to(replay.duration());
+        assert!(player.finished());
+        assert_eq!(player.board.cells(), board.cells());
+        assert_eq!(player.board.game_state, board.game_state);
+    }
+}
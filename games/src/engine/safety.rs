@@ -0,0 +1,32 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+// How much of the board around the first opened cell is kept free of mines.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, EnumIter, Serialize, Deserialize,
+)]
+pub enum Safety {
+    // The first click can be a mine
+    Unprotected,
+    // Only the cell itself
+    SafeCell,
+    // The cell and its neighbours, so it always opens up
+    #[default]
+    SafeOpening,
+    // Also the neighbours' neighbours, so every cell around it is a zero too
+    ZeroOpening,
+}
+
+impl fmt::Display for Safety {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Safety::Unprotected => "Unprotected",
+            Safety::SafeCell => "Safe cell",
+            Safety::SafeOpening => "Safe opening",
+            Safety::ZeroOpening => "Zero opening",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,641 @@
+use super::safety::Safety;
+use super::{Board, GameState};
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+type Pos = (usize, usize);
+
+// Frontier components bigger than this, or needing more search steps than
+// ENUMERATION_STEPS, are left to the cheaper rules.
+const MAX_COMPONENT: usize = 64;
+const ENUMERATION_STEPS: usize = 100_000;
+
+// Cells whose content follows from the revealed numbers alone. Flags placed by
+// the player are not trusted, they're treated as hidden cells.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Deductions {
+    pub safe: BTreeSet<Pos>,
+    pub mines: BTreeSet<Pos>,
+}
+
+impl Deductions {
+    fn is_known(&self, pos: &Pos) -> bool {
+        self.safe.contains(pos) || self.mines.contains(pos)
+    }
+}
+
+// A revealed number: exactly `mines` of the `cells` around it are mines.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Constraint {
+    cells: BTreeSet<Pos>,
+    mines: usize,
+}
+
+fn hidden(board: &Board, (x, y): Pos) -> bool {
+    board.cell(x, y).flags() != 0
+}
+
+fn constraints(board: &Board, known: &Deductions) -> Vec<Constraint> {
+    let mut out = vec![];
+    for (x, y) in
+        (0..board.rows as usize).flat_map(|x| (0..board.cols as usize).map(move |y| (x, y)))
+    {
+        let cell = board.cell(x, y);
+        if cell.flags() != 0 || cell.is_mine() {
+            continue;
+        }
+        let mut cells = BTreeSet::new();
+        let mut found = 0;
+        for pos in board.neighbours(x, y) {
+            if !hidden(board, pos) || known.safe.contains(&pos) {
+                continue;
+            }
+            if known.mines.contains(&pos) {
+                found += 1;
+            } else {
+                cells.insert(pos);
+            }
+        }
+        if !cells.is_empty() {
+            out.push(Constraint {
+                cells,
+                mines: (cell.value() as usize).saturating_sub(found),
+            });
+        }
+    }
+    out.sort_unstable_by(|a, b| a.cells.cmp(&b.cells));
+    out.dedup();
+    out
+}
+
+// Trivial rule: a number already satisfied makes the rest safe, a number with
+// as many hidden cells as missing mines makes them all mines.
+fn single_rule(constraints: &[Constraint], known: &mut Deductions) -> bool {
+    let mut changed = false;
+    for c in constraints {
+        if c.mines == 0 {
+            changed |= c.cells.iter().any(|&pos| known.safe.insert(pos));
+        } else if c.mines == c.cells.len() {
+            changed |= c.cells.iter().any(|&pos| known.mines.insert(pos));
+        }
+    }
+    changed
+}
+
+// Pair rule: for two overlapping numbers A and B, if A needs so many more mines
+// than B that every cell of A outside B must be a mine, then every cell of B
+// outside A is safe. Covers the subset case as well.
+fn pair_rule(constraints: &[Constraint], known: &mut Deductions) -> bool {
+    let mut by_cell: HashMap<Pos, Vec<usize>> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for &pos in &c.cells {
+            by_cell.entry(pos).or_default().push(i);
+        }
+    }
+    let mut changed = false;
+    let mut seen = BTreeSet::new();
+    for ids in by_cell.values() {
+        for &i in ids {
+            for &j in ids {
+                if i == j || !seen.insert((i, j)) {
+                    continue;
+                }
+                let (a, b) = (&constraints[i], &constraints[j]);
+                let a_only = a.cells.difference(&b.cells).collect::<Vec<_>>();
+                if a.mines < b.mines || a.mines - b.mines != a_only.len() {
+                    continue;
+                }
+                for &pos in a_only {
+                    changed |= known.mines.insert(pos);
+                }
+                for &pos in b.cells.difference(&a.cells) {
+                    changed |= known.safe.insert(pos);
+                }
+            }
+        }
+    }
+    changed
+}
+
+pub(super) fn unknown_cells(board: &Board, known: &Deductions) -> Vec<Pos> {
+    (0..board.rows as usize)
+        .flat_map(|x| (0..board.cols as usize).map(move |y| (x, y)))
+        .filter(|&pos| hidden(board, pos) && !known.is_known(&pos))
+        .collect()
+}
+
+// Constraints linked through shared cells; what happens in one component says
+// nothing about the others, apart from the total mine count.
+pub(super) struct Component {
+    pub cells: Vec<Pos>,
+    constraints: Vec<(Vec<usize>, usize)>,
+}
+
+fn components(constraints: &[Constraint]) -> Vec<Component> {
+    let mut index: HashMap<Pos, usize> = HashMap::new();
+    let mut parent: Vec<usize> = vec![];
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for c in constraints {
+        let mut first = None;
+        for pos in &c.cells {
+            let i = *index.entry(*pos).or_insert_with(|| {
+                parent.push(parent.len());
+                parent.len() - 1
+            });
+            match first {
+                None => first = Some(i),
+                Some(first) => {
+                    let (a, b) = (root(&mut parent, first), root(&mut parent, i));
+                    parent[a] = b;
+                }
+            }
+        }
+    }
+    let mut groups: HashMap<usize, Component> = HashMap::new();
+    for c in constraints {
+        let first = index[c.cells.iter().next().expect("constraints aren't empty")];
+        let group = groups
+            .entry(root(&mut parent, first))
+            .or_insert_with(|| Component {
+                cells: vec![],
+                constraints: vec![],
+            });
+        let mut ids = vec![];
+        for pos in &c.cells {
+            let id = match group.cells.iter().position(|p| p == pos) {
+                Some(id) => id,
+                None => {
+                    group.cells.push(*pos);
+                    group.cells.len() - 1
+                }
+            };
+            ids.push(id);
+        }
+        group.constraints.push((ids, c.mines));
+    }
+    let mut out = groups.into_values().collect::<Vec<_>>();
+    out.sort_unstable_by(|a, b| a.cells.cmp(&b.cells));
+    out
+}
+
+// Every way of placing mines on a component, grouped by how many mines are
+// used: by_mines[k] holds the number of such placements and, for each cell,
+// in how many of them it's a mine.
+pub(super) struct Solutions {
+    pub by_mines: Vec<(u64, Vec<u64>)>,
+}
+
+impl Solutions {
+    pub fn feasible(&self) -> impl Iterator<Item = usize> + '_ {
+        self.by_mines
+            .iter()
+            .enumerate()
+            .filter(|(_, (count, _))| *count > 0)
+            .map(|(k, _)| k)
+    }
+}
+
+fn enumerate(component: &Component) -> Option<Solutions> {
+    let n = component.cells.len();
+    if n > MAX_COMPONENT {
+        return None;
+    }
+    let mut by_cell = vec![vec![]; n];
+    for (i, (ids, _)) in component.constraints.iter().enumerate() {
+        for &id in ids {
+            by_cell[id].push(i);
+        }
+    }
+    struct Search<'a> {
+        constraints: &'a [(Vec<usize>, usize)],
+        by_cell: Vec<Vec<usize>>,
+        placed: Vec<usize>,
+        open: Vec<usize>,
+        mine: Vec<bool>,
+        out: Vec<(u64, Vec<u64>)>,
+        steps: usize,
+    }
+    impl Search<'_> {
+        fn fits(&self, id: usize) -> bool {
+            self.by_cell[id].iter().all(|&c| {
+                let mines = self.constraints[c].1;
+                self.placed[c] <= mines && self.placed[c] + self.open[c] >= mines
+            })
+        }
+        fn set(&mut self, id: usize, mine: bool, undo: bool) {
+            for &c in &self.by_cell[id] {
+                match undo {
+                    false => self.open[c] -= 1,
+                    true => self.open[c] += 1,
+                }
+                if mine {
+                    match undo {
+                        false => self.placed[c] += 1,
+                        true => self.placed[c] -= 1,
+                    }
+                }
+            }
+            self.mine[id] = mine && !undo;
+        }
+        fn run(&mut self, id: usize) -> bool {
+            self.steps += 1;
+            if self.steps > ENUMERATION_STEPS {
+                return false;
+            }
+            if id == self.mine.len() {
+                let k = self.mine.iter().filter(|&&m| m).count();
+                let (count, cells) = &mut self.out[k];
+                *count += 1;
+                for (cell, &mine) in cells.iter_mut().zip(&self.mine) {
+                    *cell += mine as u64;
+                }
+                return true;
+            }
+            for mine in [false, true] {
+                self.set(id, mine, false);
+                if self.fits(id) && !self.run(id + 1) {
+                    return false;
+                }
+                self.set(id, mine, true);
+            }
+            true
+        }
+    }
+    let mut search = Search {
+        constraints: &component.constraints,
+        by_cell,
+        placed: vec![0; component.constraints.len()],
+        open: component
+            .constraints
+            .iter()
+            .map(|(ids, _)| ids.len())
+            .collect(),
+        mine: vec![false; n],
+        out: vec![(0, vec![0; n]); n + 1],
+        steps: 0,
+    };
+    match search.run(0) {
+        true => Some(Solutions {
+            by_mines: search.out,
+        }),
+        false => None,
+    }
+}
+
+pub(super) fn solve_components(
+    board: &Board,
+    known: &Deductions,
+) -> Vec<(Component, Option<Solutions>)> {
+    components(&constraints(board, known))
+        .into_iter()
+        .map(|component| {
+            let solutions = enumerate(&component);
+            (component, solutions)
+        })
+        .collect()
+}
+
+// Enumeration rule: try every mine placement on each frontier component that
+// agrees with the numbers and with the mines left, a cell that is a mine in
+// all of them or in none is decided. So is the interior, when the frontier
+// leaves it no mines or only mines.
+fn enumeration_rule(board: &Board, known: &mut Deductions) -> bool {
+    let solved = solve_components(board, known);
+    let left = (board.mines as usize).saturating_sub(known.mines.len());
+    let frontier = solved.iter().map(|(c, _)| c.cells.len()).sum::<usize>();
+    let interior = unknown_cells(board, known).len() - frontier;
+    let bounds = solved
+        .iter()
+        .map(|(c, s)| match s {
+            Some(s) => (
+                s.feasible().min().unwrap_or(0),
+                s.feasible().max().unwrap_or(0),
+            ),
+            None => (0, c.cells.len()),
+        })
+        .collect::<Vec<_>>();
+    let (min_all, max_all) = bounds
+        .iter()
+        .fold((0, 0), |(a, b), (min, max)| (a + min, b + max));
+    let mut changed = false;
+    // The frontier needs every mine left, or leaves enough to fill the rest
+    if interior > 0 && (min_all >= left || max_all + interior <= left) {
+        let rest = unknown_cells(board, known)
+            .into_iter()
+            .filter(|pos| !solved.iter().any(|(c, _)| c.cells.contains(pos)))
+            .collect::<Vec<_>>();
+        match min_all >= left {
+            true => known.safe.extend(rest),
+            false => known.mines.extend(rest),
+        }
+        changed = true;
+    }
+    for ((component, solutions), (min, max)) in solved.iter().zip(&bounds) {
+        let solutions = match solutions {
+            Some(solutions) => solutions,
+            None => continue,
+        };
+        let (min_others, max_others) = (min_all - min, max_all - max);
+        let mut total = 0;
+        let mut mines = vec![0; component.cells.len()];
+        for k in solutions.feasible() {
+            if k + min_others > left || k + max_others + interior < left {
+                continue;
+            }
+            let (count, cells) = &solutions.by_mines[k];
+            total += count;
+            for (m, c) in mines.iter_mut().zip(cells) {
+                *m += c;
+            }
+        }
+        if total == 0 {
+            continue;
+        }
+        for (pos, m) in component.cells.iter().zip(mines) {
+            if m == 0 {
+                changed |= known.safe.insert(*pos);
+            } else if m == total {
+                changed |= known.mines.insert(*pos);
+            }
+        }
+    }
+    changed
+}
+
+// Global rule: once every remaining mine is accounted for, or every remaining
+// hidden cell must be a mine, the rest of the board follows.
+fn count_rule(board: &Board, known: &mut Deductions) -> bool {
+    let unknown = unknown_cells(board, known);
+    let left = (board.mines as usize).saturating_sub(known.mines.len());
+    if unknown.is_empty() {
+        return false;
+    }
+    if left == 0 {
+        known.safe.extend(unknown);
+        true
+    } else if left == unknown.len() {
+        known.mines.extend(unknown);
+        true
+    } else {
+        false
+    }
+}
+
+// The rules the solver knows, from the simplest to the most expensive.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Rule {
+    Single,
+    Pair,
+    Enumeration,
+    Count,
+}
+
+impl Rule {
+    const ALL: [Rule; 4] = [Rule::Single, Rule::Pair, Rule::Enumeration, Rule::Count];
+
+    fn apply(&self, board: &Board, known: &mut Deductions) -> bool {
+        match self {
+            Rule::Single => single_rule(&constraints(board, known), known),
+            Rule::Pair => pair_rule(&constraints(board, known), known),
+            Rule::Enumeration => enumeration_rule(board, known),
+            Rule::Count => count_rule(board, known),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hint {
+    // A cell that can be opened, and the hardest rule needed to see it
+    Safe(Pos, Rule),
+    // Where to make the first click, which the board keeps clear of mines
+    Opening(Pos),
+    Guess,
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::Safe(_, Rule::Single) => {
+                write!(f, "A number next to it already touches all of its mines")
+            }
+            Hint::Safe(_, Rule::Pair) => {
+                write!(f, "Comparing two overlapping numbers shows it's clear")
+            }
+            Hint::Safe(_, Rule::Enumeration) => {
+                write!(
+                    f,
+                    "Every way of placing the mines around the numbers leaves it clear"
+                )
+            }
+            Hint::Safe(_, Rule::Count) => write!(f, "Every remaining mine is accounted for"),
+            Hint::Opening(_) => write!(f, "The first click never hits a mine"),
+            Hint::Guess => write!(f, "Nothing can be deduced, a guess is required"),
+        }
+    }
+}
+
+// The rules assume one mine per cell, so nothing is deduced on multi-mine boards.
+pub fn deduce(board: &Board) -> Deductions {
+    let mut known = Deductions::default();
+    if !board.multi {
+        extend(board, &mut known, Rule::Count);
+    }
+    known
+}
+
+// Applies the rules up to `hardest` until nothing new comes out, always going
+// back to the cheapest one after a success.
+fn extend(board: &Board, known: &mut Deductions, hardest: Rule) {
+    while Rule::ALL
+        .iter()
+        .filter(|rule| **rule <= hardest)
+        .any(|rule| rule.apply(board, known))
+    {}
+}
+
+pub fn hint(board: &Board) -> Hint {
+    if !board.started() {
+        // Nothing is known about an unprotected board before the first click
+        if board.safety == Safety::Unprotected {
+            return Hint::Guess;
+        }
+        let centre = (board.rows as usize / 2, board.cols as usize / 2);
+        return Hint::Opening(centre);
+    }
+    if board.multi {
+        return Hint::Guess;
+    }
+    let mut known = Deductions::default();
+    for rule in Rule::ALL {
+        extend(board, &mut known, rule);
+        if let Some(&pos) = known.safe.iter().next() {
+            return Hint::Safe(pos, rule);
+        }
+    }
+    Hint::Guess
+}
+
+// Plays a freshly generated board from its first click using only deductions.
+// Returns None when that clears the board, otherwise the position the solver
+// got stuck in along with the mines it had found so far.
+pub(super) fn stuck(board: &Board, x: usize, y: usize) -> Option<(Board, Deductions)> {
+    let mut sim = board.clone();
+    sim.hide_all();
+    sim.click(x, y);
+    let mut known = Deductions::default();
+    loop {
+        if sim.game_state != GameState::InProgress {
+            return None;
+        }
+        extend(&sim, &mut known, Rule::Count);
+        if known.safe.is_empty() {
+            return Some((sim, known));
+        }
+        for (x, y) in std::mem::take(&mut known.safe) {
+            sim.click(x, y);
+        }
+    }
+}
+
+// Every way of placing the mines on the hidden cells that agrees with the
+// revealed numbers, for checking the rules against on small boards.
+
+#[cfg(test)]
+pub(super) fn layouts(board: &Board) -> Vec<BTreeSet<Pos>> {
+    fn place(
+        board: &Board,
+        hidden: &[Pos],
+        left: usize,
+        mines: &mut Vec<Pos>,
+        out: &mut Vec<BTreeSet<Pos>>,
+    ) {
+        if left == 0 {
+            let fits = board.cells().iter().flatten().all(|cell| {
+                cell.flags() != 0
+                    || board
+                        .neighbours(cell.x, cell.y)
+                        .filter(|pos| mines.contains(pos))
+                        .count()
+                        == cell.value() as usize
+            });
+            if fits {
+                out.push(mines.iter().copied().collect());
+            }
+            return;
+        }
+        for (i, &pos) in hidden.iter().enumerate() {
+            mines.push(pos);
+            place(board, &hidden[i + 1..], left - 1, mines, out);
+            mines.pop();
+        }
+    }
+    let hidden = board
+        .cells()
+        .iter()
+        .flatten()
+        .filter(|cell| cell.flags() != 0)
+        .map(|cell| (cell.x, cell.y))
+        .collect::<Vec<_>>();
+    let mut out = vec![];
+    place(board, &hidden, board.mines as usize, &mut vec![], &mut out);
+    out
+}
+
+// Small boards part way through, with few enough hidden cells for `layouts`.
+#[cfg(test)]
+pub(super) fn positions() -> Vec<Board> {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+    let mut out = vec![];
+    for seed in 0..40 {
+        let mut board = Board::with_seed(6, 6, 7, seed);
+        if seed % 4 == 1 {
+            board.topology = super::topology::Topology::Hex;
+        }
+        board.wrap = seed % 3 == 2;
+        board.click(2, 3);
+        loop {
+            let hidden = board
+                .cells()
+                .iter()
+                .flatten()
+                .filter(|cell| cell.flags() != 0)
+                .map(|cell| (cell.x, cell.y, cell.is_mine()))
+                .collect::<Vec<_>>();
+            if board.game_state != GameState::InProgress {
+                break;
+            }
+            if hidden.len() <= 18 {
+                out.push(board);
+                break;
+            }
+            let safe = hidden.iter().filter(|cell| !cell.2).collect::<Vec<_>>();
+            if let Some(&&(x, y, _)) = safe.choose(&mut rng) {
+                board.click(x, y);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deductions_match_brute_force() {
+        let boards = positions();
+        assert!(boards.len() > 20);
+        for board in boards {
+            let layouts = layouts(&board);
+            assert!(!layouts.is_empty());
+            let hidden = unknown_cells(&board, &Deductions::default());
+            let safe = hidden
+                .iter()
+                .filter(|pos| layouts.iter().all(|mines| !mines.contains(pos)))
+                .copied()
+                .collect::<BTreeSet<_>>();
+            let mines = hidden
+                .iter()
+                .filter(|pos| layouts.iter().all(|mines| mines.contains(pos)))
+                .copied()
+                .collect::<BTreeSet<_>>();
+            let known = deduce(&board);
+            assert_eq!(
+                (&known.safe, &known.mines),
+                (&safe, &mines),
+                "{:?}",
+                board.to_text()
+            );
+            match hint(&board) {
+                Hint::Safe(pos, _) => assert!(safe.contains(&pos)),
+                hint => assert!(safe.is_empty(), "{:?}", hint),
+            }
+        }
+    }
+
+    #[test]
+    fn first_click_hints() {
+        let mut board = Board::with_seed(9, 9, 10, 0);
+        assert_eq!(hint(&board), Hint::Opening((4, 4)));
+        board.safety = Safety::Unprotected;
+        assert_eq!(hint(&board), Hint::Guess);
+    }
+
+    #[test]
+    fn hints_name_the_rule() {
+        let board = "2x3\n*.*\n121".parse::<Board>().unwrap();
+        assert_eq!(hint(&board), Hint::Safe((0, 1), Rule::Pair));
+        let board = "2x2\n*.\n..".parse::<Board>().unwrap();
+        assert_eq!(hint(&board), Hint::Guess);
+    }
+}
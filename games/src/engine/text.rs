@@ -0,0 +1,167 @@
+use super::topology::Topology;
+use super::{Board, GameState};
+
+use std::fmt;
+use std::str::FromStr;
+
+// A plain-text position, easy to paste in chat or write down in a test:
+//
+//     3x4
+//     02F.
+//     02*.
+//     01f.
+//
+// The first line is the size, optionally followed by the same "-h" and "-w"
+// suffixes as a game id. Then one line per row with one character per cell:
+// `.` hidden, `*` mine, `F` flagged mine, `f` flag on a safe cell, `X` the
+// mine that blew up and digits for revealed cells. Question marks are written
+// as hidden cells, multi-mine boards aren't covered.
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseBoardError;
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected a size like 9x9 followed by rows of . * F f X and digits"
+        )
+    }
+}
+
+impl Board {
+    // None until the mines are laid out, or for multi-mine boards
+    pub fn to_text(&self) -> Option<String> {
+        if !self.start || self.multi {
+            return None;
+        }
+        let mut text = format!("{}x{}", self.rows, self.cols);
+        if self.topology == Topology::Hex {
+            text.push_str("-h");
+        }
+        if self.wrap {
+            text.push_str("-w");
+        }
+        for row in &self.board {
+            text.push('\n');
+            text.extend(row.iter().map(|cell| match (cell.flags(), cell.is_mine()) {
+                (4, _) => 'X',
+                (2, true) => 'F',
+                (2, false) => 'f',
+                (0, false) => char::from_digit(cell.value() as u32, 10).unwrap_or('#'),
+                (_, true) => '*',
+                (_, false) => '.',
+            }));
+        }
+        Some(text)
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let mut parts = lines.next().ok_or(ParseBoardError)?.split('-');
+        let (rows, cols) = parts
+            .next()
+            .and_then(|size| size.split_once('x'))
+            .ok_or(ParseBoardError)?;
+        let (rows, cols) = match (rows.parse::<u16>(), cols.parse::<u16>()) {
+            (Ok(rows), Ok(cols)) if rows > 0 && cols > 0 => (rows, cols),
+            _ => return Err(ParseBoardError),
+        };
+        // The rows have to match the size before a board that big gets made
+        let grid = lines
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        if grid.len() != rows as usize || grid.iter().any(|row| row.len() != cols as usize) {
+            return Err(ParseBoardError);
+        }
+        let mut board = Board::with_seed(rows, cols, 0, 0);
+        for part in parts {
+            match part {
+                "h" if board.topology == Topology::Square => board.topology = Topology::Hex,
+                "w" if !board.wrap => board.wrap = true,
+                _ => return Err(ParseBoardError),
+            }
+        }
+        let mines = (0..rows as usize)
+            .flat_map(|x| (0..cols as usize).map(move |y| (x, y)))
+            .filter(|&(x, y)| matches!(grid[x][y], '*' | 'F' | 'X'))
+            .collect::<Vec<_>>();
+        board.place_mines(&mines);
+        for (x, row) in grid.iter().enumerate() {
+            for (y, &symbol) in row.iter().enumerate() {
+                let cell = &mut board.board[x][y];
+                match symbol {
+                    '.' | '*' => (),
+                    'F' | 'f' => {
+                        cell.set_flagged(1);
+                        board.flagged_mines += 1;
+                    }
+                    'X' => {
+                        cell.set_state(4);
+                        board.game_state = GameState::Lost;
+                    }
+                    // The numbers have to agree with the mines around them
+                    _ if symbol.to_digit(10) == Some(cell.value() as u32) => {
+                        cell.set_state(0);
+                        board.clicked_cells += 1;
+                    }
+                    _ => return Err(ParseBoardError),
+                }
+            }
+        }
+        if board.game_state == GameState::InProgress && board.clicked_cells == board.safe_cells() {
+            board.game_state = GameState::Won;
+        }
+        if board.game_state != GameState::InProgress {
+            board.finish();
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for text in [
+            "3x4\n02F.\n02*.\n01f.",
+            "4x4-h\n1*1.\n1100\n0000\n0000",
+            "3x3-w\n*..\n...\n...",
+        ] {
+            let board = text.parse::<Board>().unwrap();
+            assert_eq!(board.to_text().as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn finished_positions() {
+        let won = "2x2\n1*\n11".parse::<Board>().unwrap();
+        assert_eq!(won.game_state, GameState::Won);
+        let lost = "2x2\nX.\n..".parse::<Board>().unwrap();
+        assert_eq!(lost.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn rejects_bad_positions() {
+        for text in [
+            "",
+            "3x3\n...\n...",
+            "2x2\n2*\n11",
+            "2x2\n.?\n..",
+            "2x2-x\n..\n..",
+        ] {
+            assert_eq!(text.parse::<Board>(), Err(ParseBoardError));
+        }
+    }
+
+    #[test]
+    fn size_is_checked_before_allocating() {
+        assert_eq!("60000x60000\n.".parse::<Board>(), Err(ParseBoardError));
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+// How the cells are laid out, which decides what counts as a neighbour.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, EnumIter, Serialize, Deserialize,
+)]
+pub enum Topology {
+    #[default]
+    Square,
+    // Pointy-top hexagons with every odd row shifted half a cell to the right
+    Hex,
+}
+
+const SQUARE: [(i32, i32); 9] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 0),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const HEX_EVEN: [(i32, i32); 7] = [(-1, -1), (-1, 0), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD: [(i32, i32); 7] = [(-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, 0), (1, 1)];
+
+impl Topology {
+    // Offsets to the cells around one in row `x`, including the cell itself.
+    pub fn offsets(&self, x: usize) -> &'static [(i32, i32)] {
+        match (self, x % 2) {
+            (Topology::Square, _) => &SQUARE,
+            (Topology::Hex, 0) => &HEX_EVEN,
+            (Topology::Hex, _) => &HEX_ODD,
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
@@ -0,0 +1,1248 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use strum::EnumIter;
+// Note items in board are in range [1,size²], if an item is 0, then it's treated as a blank space.
+// 1 must always be part of the original input
+
+type Pos = (usize, usize);
+pub type Grid = Vec<Vec<u16>>;
+
+// Board sizes the solver takes, a side of 6 to 16 squares
+pub const SIZES: RangeInclusive<usize> = 6..=16;
+
+// The eight knight moves, going round clockwise
+const MOVES: [(i16, i16); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+// The squares a knight can jump to from `pos`, in the order of MOVES.
+fn neighbours(size: usize, (x, y): Pos) -> impl Iterator<Item = Pos> {
+    MOVES.iter().filter_map(move |&(dx, dy)| {
+        let (x, y) = (x as i16 + dx, y as i16 + dy);
+        let inside = (0..size as i16).contains(&x) && (0..size as i16).contains(&y);
+        inside.then_some((x as usize, y as usize))
+    })
+}
+
+// Fewest knight moves between every two squares of a size x size board, the
+// square at (x, y) numbered x * size + y. Worked out once per size.
+fn distances(size: usize) -> &'static [u8] {
+    static DISTANCES: [OnceLock<Vec<u8>>; *SIZES.end() + 1] =
+        [const { OnceLock::new() }; *SIZES.end() + 1];
+    DISTANCES[size].get_or_init(|| {
+        let cells = size * size;
+        let mut dist = vec![u8::MAX; cells * cells];
+        for from in 0..cells {
+            let row = &mut dist[from * cells..(from + 1) * cells];
+            row[from] = 0;
+            let mut queue = VecDeque::from([(from / size, from % size)]);
+            while let Some(pos) = queue.pop_front() {
+                let d = row[pos.0 * size + pos.1];
+                for (x, y) in neighbours(size, pos) {
+                    if row[x * size + y] == u8::MAX {
+                        row[x * size + y] = d + 1;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+        }
+        dist
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, EnumIter)]
+pub enum Mode {
+    // Every row and column adds up to the same number
+    #[default]
+    SemiMagic,
+    Plain,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::SemiMagic => write!(f, "Semi-magic"),
+            Mode::Plain => write!(f, "Plain"),
+        }
+    }
+}
+
+// What every row and column of a semi-magic tour adds up to, 260 on 8x8.
+pub const fn magic_sum(size: usize) -> u32 {
+    (size * (size * size + 1) / 2) as u32
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Board {
+    pub board: Grid,
+    mode: Mode,
+    rsum: Vec<u32>,
+    csum: Vec<u32>,
+    rfree: Vec<usize>,
+    cfree: Vec<usize>,
+    // Where each given number sits, the tour has to go through it
+    clues: Vec<Option<Pos>>,
+    // The first given number after each number, 0 past the last one, and
+    // the last one up to it
+    upcoming: Vec<u16>,
+    previous: Vec<u16>,
+    dist: &'static [u8],
+    // The empty squares in order of the last given number they can come
+    // after, and where the ones for each number start
+    stretches: Vec<Pos>,
+    stretch_start: Vec<usize>,
+    sum: u32,
+    // Most that this many free cells of a line can still add up to
+    rem_sum: Vec<u32>,
+    // Squares tried so far, the search gives up after max_nodes
+    nodes: u64,
+    max_nodes: u64,
+    // The squares the tour hasn't been through yet, a bit per square and a
+    // number per row
+    open: [u32; 16],
+    // How many open squares each square has a knight's move away
+    free: Vec<u8>,
+    // Open squares with fewer than two open squares around them
+    lonely: usize,
+}
+
+impl Board {
+    fn new(size: usize, mode: Mode) -> Self {
+        let last = (size * size) as u32;
+        Board {
+            board: vec![vec![0; size]; size],
+            mode,
+            rsum: vec![0; size],
+            csum: vec![0; size],
+            rfree: vec![size; size],
+            cfree: vec![size; size],
+            clues: vec![None; size * size + 1],
+            upcoming: vec![0; size * size + 1],
+            previous: vec![0; size * size + 1],
+            dist: distances(size),
+            stretches: vec![],
+            stretch_start: vec![],
+            sum: magic_sum(size),
+            rem_sum: (0..size as u32)
+                .map(|free| (0..free).map(|i| last - i).sum())
+                .collect(),
+            nodes: 0,
+            max_nodes: u64::MAX,
+            open: [0; 16],
+            free: vec![0; size * size],
+            lonely: 0,
+        }
+    }
+
+    // The given numbers of `grid` in place, checked to make a tour the
+    // solver can start on.
+    pub fn with_clues(grid: &Grid, mode: Mode) -> Result<Self, SolveError> {
+        let size = grid.len();
+        if !SIZES.contains(&size) || grid.iter().any(|row| row.len() != size) {
+            return Err(SolveError::Size);
+        }
+        let mut board = Board::new(size, mode);
+        for (x, row) in grid.iter().enumerate() {
+            for (y, &val) in row.iter().enumerate() {
+                match val {
+                    0 => (),
+                    _ if val as usize > size * size => return Err(SolveError::OutOfRange(val)),
+                    _ if board.clues[val as usize].is_some() => {
+                        return Err(SolveError::Repeated(val))
+                    }
+                    _ => board.set(val, x, y),
+                }
+            }
+        }
+        match board.clues[1] {
+            Some(_) => Ok(board),
+            None => Err(SolveError::NoStart),
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.board.len()
+    }
+
+    fn dist(&self, (x, y): Pos, (to_x, to_y): Pos) -> usize {
+        let size = self.size();
+        self.dist[(x * size + y) * size * size + to_x * size + to_y] as usize
+    }
+
+    fn update(&mut self, val: u16, (x, y): Pos, flag: bool) {
+        if flag {
+            return;
+        }
+        self.rsum[x] += val as u32;
+        self.csum[y] += val as u32;
+        self.rfree[x] -= 1;
+        self.cfree[y] -= 1;
+        self.board[x][y] = val;
+    }
+    fn undo(&mut self, val: u16, (x, y): Pos, flag: bool) {
+        if flag {
+            return;
+        }
+        self.rsum[x] -= val as u32;
+        self.csum[y] -= val as u32;
+        self.rfree[x] += 1;
+        self.cfree[y] += 1;
+        self.board[x][y] = 0;
+    }
+    fn is_open(&self, (x, y): Pos) -> bool {
+        self.open[x] >> y & 1 == 1
+    }
+
+    // Keeps count of the open squares around `pos` as the tour goes through
+    // it or backs out of it.
+    fn visit(&mut self, (x, y): Pos, visited: bool) {
+        let size = self.size();
+        if visited {
+            self.open[x] &= !(1 << y);
+            if self.free[x * size + y] < 2 {
+                self.lonely -= 1;
+            }
+        }
+        for pos in neighbours(size, (x, y)) {
+            let i = pos.0 * size + pos.1;
+            let open = self.is_open(pos);
+            if visited {
+                self.free[i] -= 1;
+            }
+            if open && self.free[i] == 1 {
+                match visited {
+                    true => self.lonely += 1,
+                    false => self.lonely -= 1,
+                }
+            }
+            if !visited {
+                self.free[i] += 1;
+            }
+        }
+        if !visited {
+            self.open[x] |= 1 << y;
+            if self.free[x * size + y] < 2 {
+                self.lonely += 1;
+            }
+        }
+    }
+    fn set(&mut self, val: u16, x: usize, y: usize) {
+        self.update(val, (x, y), false);
+        self.clues[val as usize] = Some((x, y));
+    }
+
+    // Goes through every way of finishing the tour from the given numbers
+    // until `found` asks to stop, returns whether it did.
+    fn search(&mut self, found: &mut impl FnMut(&Board) -> bool) -> bool {
+        let Some(start) = self.clues[1] else {
+            return false;
+        };
+        let last = self.clues.len() - 1;
+        let given = (1..=last)
+            .filter(|&val| self.clues[val].is_some())
+            .collect::<Vec<_>>();
+        let mut upcoming = 0;
+        for val in (1..=last).rev() {
+            self.upcoming[val] = upcoming;
+            if self.clues[val].is_some() {
+                upcoming = val as u16;
+            }
+        }
+        for val in 1..=last {
+            self.previous[val] = match self.clues[val] {
+                Some(_) => val as u16,
+                None => self.previous[val - 1],
+            };
+        }
+        // A square fits between two given numbers if it's close enough to
+        // both of them, or after the last one if it's close enough to it
+        let size = self.size();
+        let mut after = vec![];
+        for pos in (0..size).flat_map(|x| (0..size).map(move |y| (x, y))) {
+            if self.board[pos.0][pos.1] != 0 {
+                continue;
+            }
+            let fits = |(i, &from): (usize, &usize)| {
+                let far = self.dist(self.clues[from]?, pos);
+                let room = match given.get(i + 1) {
+                    Some(&to) => far + self.dist(pos, self.clues[to]?) <= to - from,
+                    None => far <= last - from,
+                };
+                room.then_some(from)
+            };
+            match given.iter().enumerate().rev().find_map(fits) {
+                Some(from) => after.push((from, pos)),
+                None => return false,
+            }
+        }
+        after.sort_unstable();
+        self.stretches = after.iter().map(|&(_, pos)| pos).collect();
+        self.stretch_start = (0..=last + 1)
+            .map(|val| after.partition_point(|&(from, _)| from < val))
+            .collect();
+        for (i, free) in self.free.iter_mut().enumerate() {
+            *free = neighbours(size, (i / size, i % size)).count() as u8;
+        }
+        self.open = [0; 16];
+        self.open[..size].fill((1 << size) - 1);
+        self.lonely = self.free.iter().filter(|&&free| free < 2).count();
+        self.visit(start, true);
+        backtracking(self, start, found)
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(8, Mode::default())
+    }
+}
+
+// Why a grid can't be handed to the solver.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolveError {
+    Size,
+    NoStart,
+    OutOfRange(u16),
+    Repeated(u16),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Size => write!(
+                f,
+                "the board has to be square, from {}x{} to {}x{}",
+                SIZES.start(),
+                SIZES.start(),
+                SIZES.end(),
+                SIZES.end()
+            ),
+            SolveError::NoStart => write!(f, "the tour needs to show where 1 is"),
+            SolveError::OutOfRange(val) => write!(f, "{} is past the end of the tour", val),
+            SolveError::Repeated(val) => write!(f, "{} is written twice", val),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+// The first way of finishing the tour, None if there's none.
+pub fn solve(grid: &Grid, mode: Mode) -> Result<Option<Grid>, SolveError> {
+    let mut solution = None;
+    Board::with_clues(grid, mode)?.search(&mut |board| {
+        solution = Some(board.board.clone());
+        true
+    });
+    Ok(solution)
+}
+
+// Up to `limit` ways of finishing the tour, asking for 2 tells whether the
+// solution is unique.
+pub fn solutions(grid: &Grid, mode: Mode, limit: usize) -> Result<Vec<Grid>, SolveError> {
+    let mut solutions = vec![];
+    let mut board = Board::with_clues(grid, mode)?;
+    if limit > 0 {
+        board.search(&mut |board| {
+            solutions.push(board.board.clone());
+            solutions.len() >= limit
+        });
+    }
+    Ok(solutions)
+}
+
+// How many ways there are of finishing the tour, this can take a long while
+// with few numbers given.
+pub fn count(grid: &Grid, mode: Mode) -> Result<u64, SolveError> {
+    let mut count = 0;
+    Board::with_clues(grid, mode)?.search(&mut |_| {
+        count += 1;
+        false
+    });
+    Ok(count)
+}
+
+fn check(curr_board: &Board, (x, y): Pos, next: u16) -> bool {
+    if curr_board.board[x][y] != 0 && curr_board.board[x][y] != next {
+        return false;
+    }
+    // A given number can't turn up anywhere else
+    if curr_board.board[x][y] == 0 && curr_board.clues[next as usize].is_some() {
+        return false;
+    }
+    // The next given number has to be reachable in exactly the moves left
+    // until it, and every move changes the colour of the square
+    let upcoming = curr_board.upcoming[next as usize];
+    if let Some(clue) = curr_board.clues[upcoming as usize].filter(|_| upcoming != 0) {
+        let dist = curr_board.dist((x, y), clue);
+        let moves = (upcoming - next) as usize;
+        if dist > moves || !(moves - dist).is_multiple_of(2) {
+            return false;
+        }
+    }
+    if curr_board.board[x][y] == next || curr_board.mode == Mode::Plain {
+        return true;
+    }
+    let (sum, next) = (curr_board.sum, next as u32);
+    let (rfree, cfree) = (curr_board.rfree[x] - 1, curr_board.cfree[y] - 1);
+    if curr_board.rsum[x] + next > sum
+        || curr_board.csum[y] + next > sum
+        || curr_board.rsum[x] + next + curr_board.rem_sum[rfree] < sum
+        || curr_board.csum[y] + next + curr_board.rem_sum[cfree] < sum
+    {
+        return false;
+    }
+    true
+}
+
+// Every square a knight's move away from one of `rows`.
+fn spread(rows: &[u32; 16], size: usize) -> [u32; 16] {
+    let mut spread = [0; 16];
+    for x in 0..size {
+        let (one, two) = (rows[x] << 1 | rows[x] >> 1, rows[x] << 2 | rows[x] >> 2);
+        if x >= 1 {
+            spread[x - 1] |= two;
+        }
+        if x + 1 < size {
+            spread[x + 1] |= two;
+        }
+        if x >= 2 {
+            spread[x - 2] |= one;
+        }
+        if x + 2 < size {
+            spread[x + 2] |= one;
+        }
+    }
+    spread
+}
+
+// Whether the squares still to visit can't all be strung onto the tour now
+// that it went from `prev` to `head`: one of them has no way in anymore, more
+// than one has a single way in or out, which only the square the tour ends on
+// can have, or some can't be reached at all.
+fn dead_end(curr_board: &Board, prev: Pos, head: Pos) -> bool {
+    let size = curr_board.size();
+    let last = curr_board.clues.len() - 1;
+    let reached = curr_board.board[head.0][head.1] as usize;
+    if reached == last {
+        return false;
+    }
+    let next_to_head = |(x, y): Pos| {
+        let (dx, dy) = (x.abs_diff(head.0), y.abs_diff(head.1));
+        dx * dx + dy * dy == 5
+    };
+    let free = |(x, y): Pos| curr_board.free[x * size + y];
+    // Only squares around where the tour just left can have lost their last way in
+    if neighbours(size, prev)
+        .any(|pos| curr_board.is_open(pos) && free(pos) == 0 && !next_to_head(pos))
+    {
+        return true;
+    }
+    // Lonely squares next to the head still have a way in from it
+    let ends = curr_board.lonely
+        - neighbours(size, head)
+            .filter(|&pos| curr_board.is_open(pos) && free(pos) == 1)
+            .count();
+    if ends > 1 {
+        return true;
+    }
+    if let Some(end) = curr_board.clues[last] {
+        if ends == 1 && free(end) + next_to_head(end) as u8 >= 2 {
+            return true;
+        }
+    }
+    // Every open square still has to fit in before the next given number,
+    // or between two later ones
+    let (since, up) = (
+        curr_board.previous[reached] as usize,
+        curr_board.upcoming[reached] as usize,
+    );
+    let (until, room) = match curr_board.clues[up].filter(|_| up != 0) {
+        Some(clue) => (up, Some(clue)),
+        None => (last + 1, None),
+    };
+    let moves = until.min(last) - reached;
+    let stretch =
+        &curr_board.stretches[curr_board.stretch_start[since]..curr_board.stretch_start[until]];
+    if stretch.iter().any(|&pos| {
+        let far = curr_board.dist(head, pos) + room.map_or(0, |clue| curr_board.dist(pos, clue));
+        curr_board.is_open(pos) && far > moves
+    }) {
+        return true;
+    }
+    // The ones that only fitted before the given number just reached got
+    // left behind
+    if since == reached && reached > 1 {
+        let before = curr_board.previous[reached - 1] as usize;
+        let stretch = &curr_board.stretches
+            [curr_board.stretch_start[before]..curr_board.stretch_start[reached]];
+        if stretch.iter().any(|&pos| curr_board.is_open(pos)) {
+            return true;
+        }
+    }
+    // Spreads out from the head a knight's move at a time, through open
+    // squares only
+    let mut rows = [0; 16];
+    rows[head.0] = 1 << head.1;
+    let mut reach = [0; 16];
+    let mut count = 0;
+    while count < last - reached {
+        let spread = spread(&rows, size);
+        let mut grown = 0;
+        for x in 0..size {
+            rows[x] = spread[x] & curr_board.open[x] & !reach[x];
+            reach[x] |= rows[x];
+            grown += rows[x].count_ones() as usize;
+        }
+        if grown == 0 {
+            return true;
+        }
+        count += grown;
+    }
+    false
+}
+
+fn backtracking(
+    curr_board: &mut Board,
+    (x, y): Pos,
+    found: &mut impl FnMut(&Board) -> bool,
+) -> bool {
+    curr_board.nodes += 1;
+    if curr_board.nodes > curr_board.max_nodes {
+        return true;
+    }
+    let next = curr_board.board[x][y] + 1;
+    if next as usize >= curr_board.clues.len() {
+        // Lines made up only of given numbers never got checked on the way
+        let mut sums = curr_board.rsum.iter().chain(&curr_board.csum);
+        let magic = curr_board.mode == Mode::Plain || sums.all(|&sum| sum == curr_board.sum);
+        return magic && found(curr_board);
+    }
+    // Squares with the fewest ways on first (Warnsdorff's rule), they're
+    // the likeliest to get cut off
+    let size = curr_board.size();
+    let mut moves = [(0, (0, 0)); 8];
+    let mut len = 0;
+    for pos in neighbours(size, (x, y)) {
+        if check(curr_board, pos, next) {
+            moves[len] = (curr_board.free[pos.0 * size + pos.1], pos);
+            len += 1;
+        }
+    }
+    moves[..len].sort_unstable_by_key(|&(onward, _)| onward);
+    for &(_, pos) in &moves[..len] {
+        let flag = curr_board.board[pos.0][pos.1] == next;
+        curr_board.update(next, pos, flag);
+        curr_board.visit(pos, true);
+        if !dead_end(curr_board, (x, y), pos) && backtracking(curr_board, pos, found) {
+            return true;
+        }
+        curr_board.visit(pos, false);
+        curr_board.undo(next, pos, flag);
+    }
+    false
+}
+
+// Whether a filled in grid is a complete tour, with every row and column
+// adding up to the magic sum for semi-magic ones.
+pub fn is_tour(grid: &Grid, mode: Mode) -> bool {
+    let size = grid.len();
+    if size == 0 || grid.iter().any(|row| row.len() != size) {
+        return false;
+    }
+    let mut pos = vec![None; size * size + 1];
+    for (x, row) in grid.iter().enumerate() {
+        for (y, &val) in row.iter().enumerate() {
+            match pos.get_mut(val as usize) {
+                Some(slot @ None) if val != 0 => *slot = Some((x, y)),
+                _ => return false,
+            }
+        }
+    }
+    let sums = mode == Mode::Plain
+        || (0..size).all(|i| {
+            grid[i].iter().map(|&val| val as u32).sum::<u32>() == magic_sum(size)
+                && grid.iter().map(|row| row[i] as u32).sum::<u32>() == magic_sum(size)
+        });
+    sums && pos[1..].windows(2).all(|pair| match pair {
+        [Some(from), Some(to)] => neighbours(size, *from).any(|pos| pos == *to),
+        _ => false,
+    })
+}
+
+// Semi-magic 8x8 tours to start from, each one standing for its symmetries
+// as well. William Beverley's from 1848 comes first, the rest were reached
+// from it and a few other old tours by letting the solver reroute parts of
+// them. There are 140 in all, counting symmetries once.
+const SEMI_MAGIC: [[[u16; 8]; 8]; 26] = [
+    [
+        [1, 30, 47, 52, 5, 28, 43, 54],
+        [48, 51, 2, 29, 44, 53, 6, 27],
+        [31, 46, 49, 4, 25, 8, 55, 42],
+        [50, 3, 32, 45, 56, 41, 26, 7],
+        [33, 62, 15, 20, 9, 24, 39, 58],
+        [16, 19, 34, 61, 40, 57, 10, 23],
+        [63, 14, 17, 36, 21, 12, 59, 38],
+        [18, 35, 64, 13, 60, 37, 22, 11],
+    ],
+    [
+        [1, 30, 47, 52, 5, 28, 43, 54],
+        [48, 51, 2, 29, 44, 53, 26, 7],
+        [31, 46, 49, 4, 27, 6, 55, 42],
+        [50, 3, 32, 45, 56, 41, 8, 25],
+        [33, 62, 15, 20, 9, 24, 57, 40],
+        [16, 19, 34, 61, 38, 59, 10, 23],
+        [63, 14, 17, 36, 21, 12, 39, 58],
+        [18, 35, 64, 13, 60, 37, 22, 11],
+    ],
+    [
+        [1, 30, 47, 52, 5, 28, 43, 54],
+        [48, 51, 2, 29, 44, 53, 6, 27],
+        [31, 46, 49, 4, 7, 26, 55, 42],
+        [50, 3, 32, 45, 40, 57, 24, 9],
+        [33, 62, 15, 20, 25, 8, 41, 56],
+        [16, 19, 34, 61, 58, 39, 10, 23],
+        [63, 14, 17, 36, 21, 12, 59, 38],
+        [18, 35, 64, 13, 60, 37, 22, 11],
+    ],
+    [
+        [1, 30, 47, 52, 5, 28, 43, 54],
+        [48, 51, 2, 29, 44, 53, 6, 27],
+        [31, 46, 49, 4, 7, 26, 55, 42],
+        [50, 3, 32, 45, 56, 41, 8, 25],
+        [33, 62, 15, 20, 9, 24, 57, 40],
+        [16, 19, 34, 61, 58, 39, 10, 23],
+        [63, 14, 17, 36, 21, 12, 59, 38],
+        [18, 35, 64, 13, 60, 37, 22, 11],
+    ],
+    [
+        [15, 22, 39, 64, 17, 2, 59, 42],
+        [38, 63, 16, 21, 60, 41, 18, 3],
+        [23, 14, 61, 40, 1, 20, 43, 58],
+        [62, 37, 24, 13, 44, 57, 4, 19],
+        [25, 12, 35, 52, 29, 6, 45, 56],
+        [36, 51, 26, 9, 48, 55, 30, 5],
+        [11, 34, 49, 28, 53, 32, 7, 46],
+        [50, 27, 10, 33, 8, 47, 54, 31],
+    ],
+    [
+        [15, 30, 53, 40, 3, 42, 51, 26],
+        [38, 55, 14, 29, 52, 27, 2, 43],
+        [31, 16, 39, 54, 41, 4, 25, 50],
+        [56, 37, 32, 13, 28, 49, 44, 1],
+        [17, 12, 57, 36, 45, 24, 5, 64],
+        [58, 33, 10, 19, 8, 61, 48, 23],
+        [11, 18, 35, 60, 21, 46, 63, 6],
+        [34, 59, 20, 9, 62, 7, 22, 47],
+    ],
+    [
+        [15, 30, 53, 40, 3, 42, 51, 26],
+        [38, 55, 14, 29, 52, 27, 2, 43],
+        [31, 16, 39, 54, 41, 4, 25, 50],
+        [56, 37, 32, 13, 28, 49, 44, 1],
+        [17, 12, 57, 36, 45, 24, 5, 64],
+        [58, 33, 20, 9, 62, 7, 48, 23],
+        [11, 18, 35, 60, 21, 46, 63, 6],
+        [34, 59, 10, 19, 8, 61, 22, 47],
+    ],
+    [
+        [2, 11, 58, 51, 14, 39, 54, 31],
+        [59, 50, 3, 12, 53, 30, 15, 38],
+        [10, 1, 52, 57, 40, 13, 32, 55],
+        [49, 60, 9, 4, 29, 56, 37, 16],
+        [64, 5, 24, 45, 20, 41, 28, 33],
+        [23, 48, 61, 8, 25, 36, 17, 42],
+        [6, 63, 46, 21, 44, 19, 34, 27],
+        [47, 22, 7, 62, 35, 26, 43, 18],
+    ],
+    [
+        [2, 11, 58, 51, 30, 39, 54, 15],
+        [59, 50, 3, 12, 53, 14, 31, 38],
+        [10, 1, 52, 57, 40, 29, 16, 55],
+        [49, 60, 9, 4, 13, 56, 37, 32],
+        [64, 5, 24, 45, 36, 41, 28, 17],
+        [23, 48, 61, 8, 25, 20, 33, 42],
+        [6, 63, 46, 21, 44, 35, 18, 27],
+        [47, 22, 7, 62, 19, 26, 43, 34],
+    ],
+    [
+        [2, 27, 42, 51, 40, 29, 54, 15],
+        [43, 50, 3, 28, 53, 14, 31, 38],
+        [26, 1, 52, 41, 30, 39, 16, 55],
+        [49, 44, 25, 4, 13, 56, 37, 32],
+        [24, 5, 64, 45, 36, 17, 12, 57],
+        [63, 48, 21, 8, 59, 10, 33, 18],
+        [6, 23, 46, 61, 20, 35, 58, 11],
+        [47, 62, 7, 22, 9, 60, 19, 34],
+    ],
+    [
+        [2, 27, 42, 51, 40, 29, 54, 15],
+        [43, 50, 3, 28, 53, 14, 31, 38],
+        [26, 1, 52, 41, 30, 39, 16, 55],
+        [49, 44, 25, 4, 13, 56, 37, 32],
+        [24, 5, 64, 45, 36, 17, 12, 57],
+        [63, 48, 7, 22, 9, 60, 33, 18],
+        [6, 23, 46, 61, 20, 35, 58, 11],
+        [47, 62, 21, 8, 59, 10, 19, 34],
+    ],
+    [
+        [2, 27, 50, 43, 6, 23, 62, 47],
+        [51, 42, 1, 26, 63, 48, 7, 22],
+        [28, 3, 44, 49, 24, 5, 46, 61],
+        [41, 52, 25, 4, 45, 64, 21, 8],
+        [14, 29, 40, 53, 20, 9, 60, 35],
+        [39, 54, 13, 32, 57, 36, 19, 10],
+        [30, 15, 56, 37, 12, 17, 34, 59],
+        [55, 38, 31, 16, 33, 58, 11, 18],
+    ],
+    [
+        [2, 27, 50, 43, 6, 23, 62, 47],
+        [51, 42, 1, 26, 63, 48, 7, 22],
+        [28, 3, 44, 49, 24, 5, 46, 61],
+        [41, 52, 25, 4, 45, 64, 21, 8],
+        [54, 29, 40, 13, 36, 9, 60, 19],
+        [39, 14, 53, 32, 57, 20, 35, 10],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 27, 52, 41, 14, 39, 54, 31],
+        [43, 50, 3, 28, 53, 30, 15, 38],
+        [26, 1, 42, 51, 40, 13, 32, 55],
+        [49, 44, 25, 4, 29, 56, 37, 16],
+        [24, 5, 64, 45, 60, 17, 12, 33],
+        [63, 48, 7, 22, 9, 36, 57, 18],
+        [6, 23, 46, 61, 20, 59, 34, 11],
+        [47, 62, 21, 8, 35, 10, 19, 58],
+    ],
+    [
+        [2, 27, 52, 41, 30, 39, 54, 15],
+        [43, 50, 3, 28, 53, 14, 31, 38],
+        [26, 1, 42, 51, 40, 29, 16, 55],
+        [49, 44, 25, 4, 13, 56, 37, 32],
+        [24, 5, 64, 45, 36, 17, 12, 57],
+        [63, 48, 21, 8, 59, 10, 33, 18],
+        [6, 23, 46, 61, 20, 35, 58, 11],
+        [47, 62, 7, 22, 9, 60, 19, 34],
+    ],
+    [
+        [2, 27, 52, 41, 30, 39, 54, 15],
+        [43, 50, 3, 28, 53, 14, 31, 38],
+        [26, 1, 42, 51, 40, 29, 16, 55],
+        [49, 44, 25, 4, 13, 56, 37, 32],
+        [24, 5, 64, 45, 36, 17, 12, 57],
+        [63, 48, 7, 22, 9, 60, 33, 18],
+        [6, 23, 46, 61, 20, 35, 58, 11],
+        [47, 62, 21, 8, 59, 10, 19, 34],
+    ],
+    [
+        [2, 43, 50, 25, 48, 23, 6, 63],
+        [51, 26, 1, 44, 5, 64, 47, 22],
+        [28, 3, 42, 49, 24, 45, 62, 7],
+        [41, 52, 27, 4, 61, 8, 21, 46],
+        [54, 29, 40, 13, 36, 19, 60, 9],
+        [39, 14, 53, 32, 57, 10, 35, 20],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 50, 25, 48, 23, 6, 63],
+        [51, 26, 1, 44, 5, 64, 47, 22],
+        [42, 3, 28, 49, 24, 45, 62, 7],
+        [27, 52, 41, 4, 61, 8, 21, 46],
+        [40, 29, 54, 13, 36, 19, 60, 9],
+        [53, 14, 39, 32, 57, 10, 35, 20],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 50, 25, 64, 23, 6, 47],
+        [51, 26, 1, 44, 5, 48, 63, 22],
+        [28, 3, 42, 49, 24, 61, 46, 7],
+        [41, 52, 27, 4, 45, 8, 21, 62],
+        [54, 29, 40, 13, 36, 19, 60, 9],
+        [39, 14, 53, 32, 57, 10, 35, 20],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 50, 25, 64, 23, 6, 47],
+        [51, 26, 1, 44, 5, 48, 63, 22],
+        [28, 3, 42, 49, 24, 7, 46, 61],
+        [41, 52, 27, 4, 45, 62, 21, 8],
+        [14, 29, 40, 53, 20, 9, 60, 35],
+        [39, 54, 13, 32, 57, 36, 19, 10],
+        [30, 15, 56, 37, 12, 17, 34, 59],
+        [55, 38, 31, 16, 33, 58, 11, 18],
+    ],
+    [
+        [2, 43, 50, 25, 64, 23, 6, 47],
+        [51, 26, 1, 44, 5, 48, 63, 22],
+        [28, 3, 42, 49, 24, 7, 46, 61],
+        [41, 52, 27, 4, 45, 62, 21, 8],
+        [54, 29, 40, 13, 36, 9, 60, 19],
+        [39, 14, 53, 32, 57, 20, 35, 10],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 50, 25, 64, 23, 6, 47],
+        [51, 26, 1, 44, 5, 48, 63, 22],
+        [42, 3, 28, 49, 24, 61, 46, 7],
+        [27, 52, 41, 4, 45, 8, 21, 62],
+        [40, 29, 54, 13, 36, 19, 60, 9],
+        [53, 14, 39, 32, 57, 10, 35, 20],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 50, 25, 64, 23, 6, 47],
+        [51, 26, 1, 44, 5, 48, 63, 22],
+        [42, 3, 28, 49, 24, 7, 46, 61],
+        [27, 52, 41, 4, 45, 62, 21, 8],
+        [40, 29, 54, 13, 36, 9, 60, 19],
+        [53, 14, 39, 32, 57, 20, 35, 10],
+        [30, 55, 16, 37, 12, 33, 18, 59],
+        [15, 38, 31, 56, 17, 58, 11, 34],
+    ],
+    [
+        [2, 43, 58, 19, 30, 39, 22, 47],
+        [59, 18, 3, 44, 21, 46, 31, 38],
+        [42, 1, 20, 57, 40, 29, 48, 23],
+        [17, 60, 41, 4, 45, 24, 37, 32],
+        [64, 5, 56, 13, 36, 9, 28, 49],
+        [55, 16, 61, 8, 25, 52, 33, 10],
+        [6, 63, 14, 53, 12, 35, 50, 27],
+        [15, 54, 7, 62, 51, 26, 11, 34],
+    ],
+    [
+        [2, 43, 58, 19, 46, 39, 22, 31],
+        [59, 18, 3, 44, 21, 30, 47, 38],
+        [42, 1, 20, 57, 40, 45, 32, 23],
+        [17, 60, 41, 4, 29, 24, 37, 48],
+        [64, 5, 56, 13, 52, 9, 28, 33],
+        [55, 16, 61, 8, 25, 36, 49, 10],
+        [6, 63, 14, 53, 12, 51, 34, 27],
+        [15, 54, 7, 62, 35, 26, 11, 50],
+    ],
+    [
+        [2, 43, 58, 19, 56, 45, 6, 31],
+        [59, 18, 3, 44, 5, 30, 47, 54],
+        [42, 1, 20, 57, 46, 55, 32, 7],
+        [17, 60, 41, 4, 29, 8, 53, 48],
+        [64, 21, 16, 37, 52, 33, 28, 9],
+        [15, 40, 61, 24, 11, 26, 49, 34],
+        [22, 63, 38, 13, 36, 51, 10, 27],
+        [39, 14, 23, 62, 25, 12, 35, 50],
+    ],
+];
+
+// The tour turned, mirrored and/or walked backwards.
+fn symmetry(tour: &Grid, (transpose, flip_x, flip_y, reverse): (bool, bool, bool, bool)) -> Grid {
+    let size = tour.len();
+    let mut turned = vec![vec![0; size]; size];
+    for (x, row) in turned.iter_mut().enumerate() {
+        for (y, val) in row.iter_mut().enumerate() {
+            let (x, y) = if transpose { (y, x) } else { (x, y) };
+            let x = if flip_x { size - 1 - x } else { x };
+            let y = if flip_y { size - 1 - y } else { y };
+            *val = if reverse {
+                (size * size + 1) as u16 - tour[x][y]
+            } else {
+                tour[x][y]
+            };
+        }
+    }
+    turned
+}
+
+// Squares a knight on `pos` could still jump to.
+fn onward(grid: &Grid, pos: Pos) -> usize {
+    neighbours(grid.len(), pos)
+        .filter(|&(x, y)| grid[x][y] == 0)
+        .count()
+}
+
+// A plain tour from a random square, always jumping to where the knight has
+// the fewest ways on (Warnsdorff's rule) and breaking ties at random. This
+// gets stuck now and then, None when it does.
+fn walk(rng: &mut impl Rng, size: usize) -> Option<Grid> {
+    let mut grid = vec![vec![0; size]; size];
+    let mut pos = (rng.gen_range(0..size), rng.gen_range(0..size));
+    grid[pos.0][pos.1] = 1;
+    for val in 2..=(size * size) as u16 {
+        let free = neighbours(size, pos)
+            .filter(|&(x, y)| grid[x][y] == 0)
+            .collect::<Vec<_>>();
+        let fewest = free.iter().map(|&pos| onward(&grid, pos)).min()?;
+        let best = free
+            .into_iter()
+            .filter(|&pos| onward(&grid, pos) == fewest)
+            .collect::<Vec<_>>();
+        pos = *best.choose(rng)?;
+        grid[pos.0][pos.1] = val;
+    }
+    Some(grid)
+}
+
+// Whether there are tours of that size and mode to make puzzles from.
+// Semi-magic ones only come in 8x8, the only size with known tours.
+pub fn has_tours(size: usize, mode: Mode) -> bool {
+    match mode {
+        Mode::SemiMagic => size == 8,
+        Mode::Plain => SIZES.contains(&size),
+    }
+}
+
+// A whole tour to take clues away from
+fn tour(rng: &mut impl Rng, size: usize, mode: Mode) -> Option<Grid> {
+    match mode {
+        Mode::SemiMagic if has_tours(size, mode) => {
+            let tour = SEMI_MAGIC.choose(rng)?;
+            let tour = tour.iter().map(|row| row.to_vec()).collect();
+            Some(symmetry(&tour, rng.gen()))
+        }
+        Mode::SemiMagic => None,
+        Mode::Plain => (0..100).find_map(|_| walk(rng, size)),
+    }
+}
+
+// Squares a uniqueness check on 8x8 may try before the clue it was for is
+// kept, which bounds how long generating a puzzle takes. Bigger boards get
+// less per check as they have more clues to check.
+const MAX_NODES: u64 = 50_000;
+
+// Squares tried to make sure the grid has exactly one solution, None if it
+// has more or the search ran out of budget.
+fn effort(grid: &Grid, mode: Mode, max_nodes: u64) -> Option<u64> {
+    let mut board = Board::with_clues(grid, mode).ok()?;
+    board.max_nodes = max_nodes;
+    let mut found = 0;
+    board.search(&mut |_| {
+        found += 1;
+        found > 1
+    });
+    (found == 1 && board.nodes <= max_nodes).then_some(board.nodes)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, EnumIter)]
+pub enum Rating {
+    Easy,
+    Medium,
+    Hard,
+    Fiendish,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Rating {
+    // Whichever of the clue count and the search effort makes it harder.
+    // Clues count in 64ths of the board, as many as on 8x8.
+    fn new(clues: usize, cells: usize, effort: u64) -> Self {
+        let by_clues = match clues * 64 / cells {
+            32.. => Rating::Easy,
+            26..=31 => Rating::Medium,
+            21..=25 => Rating::Hard,
+            _ => Rating::Fiendish,
+        };
+        let by_effort = match effort {
+            0..=500 => Rating::Easy,
+            501..=5_000 => Rating::Medium,
+            5_001..=25_000 => Rating::Hard,
+            _ => Rating::Fiendish,
+        };
+        by_clues.max(by_effort)
+    }
+
+    // Where the generator stops taking clues away
+    const fn min_clues(&self, cells: usize) -> usize {
+        let share = match self {
+            Rating::Easy => 32,
+            Rating::Medium => 26,
+            Rating::Hard => 21,
+            Rating::Fiendish => 1,
+        };
+        (share * cells).div_ceil(64)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Puzzle {
+    pub grid: Grid,
+    pub solution: Grid,
+    pub mode: Mode,
+    pub clues: usize,
+    // Squares the solver tried to be sure of the solution
+    pub effort: u64,
+    pub rating: Rating,
+}
+
+impl Puzzle {
+    pub fn size(&self) -> usize {
+        self.grid.len()
+    }
+}
+
+// Starts from a whole tour and takes clues away in random order for as long
+// as the solution stays unique, leaving in any clue that would make it
+// harder than `target`. 1 always stays, the solver starts from it. None if
+// there's no tour of that size and mode to start from.
+pub fn generate(rng: &mut impl Rng, size: usize, mode: Mode, target: Rating) -> Option<Puzzle> {
+    if !has_tours(size, mode) {
+        return None;
+    }
+    let max_nodes = MAX_NODES * 64 / (size * size) as u64;
+    let solution = tour(rng, size, mode)?;
+    let mut grid = solution.clone();
+    let mut cells = (0..size)
+        .flat_map(|x| (0..size).map(move |y| (x, y)))
+        .filter(|&(x, y)| grid[x][y] != 1)
+        .collect::<Vec<Pos>>();
+    cells.shuffle(rng);
+    let mut clues = size * size;
+    let mut last = 0;
+    for (x, y) in cells {
+        if clues <= target.min_clues(size * size) {
+            break;
+        }
+        let val = std::mem::replace(&mut grid[x][y], 0);
+        match effort(&grid, mode, max_nodes) {
+            Some(effort) if Rating::new(clues - 1, size * size, effort) <= target => {
+                clues -= 1;
+                last = effort;
+            }
+            _ => grid[x][y] = val,
+        }
+    }
+    Some(Puzzle {
+        grid,
+        solution,
+        mode,
+        clues,
+        effort: last,
+        rating: Rating::new(clues, size * size, last),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn semi_magic(i: usize) -> Grid {
+        SEMI_MAGIC[i].iter().map(|row| row.to_vec()).collect()
+    }
+
+    fn symmetries(tour: &Grid) -> Vec<Grid> {
+        (0..16)
+            .map(|i| symmetry(tour, (i & 1 != 0, i & 2 != 0, i & 4 != 0, i & 8 != 0)))
+            .collect()
+    }
+
+    // Every tour through the clues, found by trying each knight's move in
+    // turn with none of the solver's pruning.
+    fn naive_count(grid: &Grid, mode: Mode) -> u64 {
+        fn extend(grid: &mut Grid, clues: &[Option<Pos>], mode: Mode, val: usize, pos: Pos) -> u64 {
+            let size = grid.len();
+            if val == size * size {
+                return is_tour(grid, mode) as u64;
+            }
+            let next = val as u16 + 1;
+            let mut count = 0;
+            for (x, y) in neighbours(size, pos) {
+                let fits = match clues[next as usize] {
+                    Some(clue) => clue == (x, y),
+                    None => grid[x][y] == 0,
+                };
+                if fits {
+                    grid[x][y] = next;
+                    count += extend(grid, clues, mode, val + 1, (x, y));
+                    if clues[next as usize].is_none() {
+                        grid[x][y] = 0;
+                    }
+                }
+            }
+            count
+        }
+        let size = grid.len();
+        let mut clues = vec![None; size * size + 1];
+        for (x, row) in grid.iter().enumerate() {
+            for (y, &val) in row.iter().enumerate() {
+                clues[val as usize] = Some((x, y));
+            }
+        }
+        let start = clues[1].unwrap();
+        extend(&mut grid.clone(), &clues, mode, 1, start)
+    }
+
+    // `tour` with only 1 and about `share` of the other numbers left in.
+    fn clues(rng: &mut impl Rng, tour: &Grid, share: f64) -> Grid {
+        tour.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&val| match val == 1 || rng.gen_bool(share) {
+                        true => val,
+                        false => 0,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_match_naive_search() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..30 {
+            let tour = walk(&mut rng, 6).unwrap();
+            let grid = clues(&mut rng, &tour, 0.4);
+            let count = count(&grid, Mode::Plain).unwrap();
+            assert!(count >= 1);
+            assert_eq!(count, naive_count(&grid, Mode::Plain));
+        }
+        for i in 0..6 {
+            let tour = symmetry(&semi_magic(i), rng.gen());
+            let grid = clues(&mut rng, &tour, 0.6);
+            let count = count(&grid, Mode::SemiMagic).unwrap();
+            assert!(count >= 1);
+            assert_eq!(count, naive_count(&grid, Mode::SemiMagic));
+        }
+    }
+
+    #[test]
+    fn solutions_keep_the_clues() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for size in [6, 7, 10] {
+            let tour = walk(&mut rng, size).unwrap();
+            assert!(is_tour(&tour, Mode::Plain));
+            let grid = clues(&mut rng, &tour, 0.5);
+            let solution = solve(&grid, Mode::Plain).unwrap().unwrap();
+            assert!(is_tour(&solution, Mode::Plain));
+            for (row, solved) in grid.iter().zip(&solution) {
+                for (&val, &solved) in row.iter().zip(solved) {
+                    assert!(val == 0 || val == solved);
+                }
+            }
+            let all = solutions(&grid, Mode::Plain, 5).unwrap();
+            assert_eq!(all[0], solution);
+            assert!(all.len() == 5 || all.contains(&tour));
+        }
+    }
+
+    #[test]
+    fn not_tours() {
+        let tour = semi_magic(0);
+        assert!(is_tour(&tour, Mode::SemiMagic));
+        let mut swapped = tour.clone();
+        swapped[0][0] = tour[0][1];
+        swapped[0][1] = tour[0][0];
+        assert!(!is_tour(&swapped, Mode::Plain));
+        let mut unfinished = tour.clone();
+        unfinished[3][3] = 0;
+        assert!(!is_tour(&unfinished, Mode::Plain));
+        assert!(!is_tour(&vec![], Mode::Plain));
+        // A plain tour whose rows don't add up to the same number
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let plain = walk(&mut rng, 6).unwrap();
+        assert!(is_tour(&plain, Mode::Plain) && !is_tour(&plain, Mode::SemiMagic));
+    }
+
+    #[test]
+    fn bad_grids() {
+        let empty = |size| vec![vec![0; size]; size];
+        assert_eq!(solve(&empty(5), Mode::Plain), Err(SolveError::Size));
+        assert_eq!(solve(&empty(17), Mode::Plain), Err(SolveError::Size));
+        let mut ragged = empty(6);
+        ragged[2].push(0);
+        assert_eq!(solve(&ragged, Mode::Plain), Err(SolveError::Size));
+        assert_eq!(solve(&empty(6), Mode::Plain), Err(SolveError::NoStart));
+        let mut grid = empty(6);
+        grid[0][0] = 1;
+        grid[5][5] = 37;
+        assert_eq!(solve(&grid, Mode::Plain), Err(SolveError::OutOfRange(37)));
+        grid[5][5] = 1;
+        assert_eq!(solve(&grid, Mode::Plain), Err(SolveError::Repeated(1)));
+        // 2 can't be a knight's move away from 1
+        grid[5][5] = 2;
+        assert_eq!(solve(&grid, Mode::Plain), Ok(None));
+    }
+
+    #[test]
+    fn ratings() {
+        assert_eq!(Rating::new(40, 64, 100), Rating::Easy);
+        assert_eq!(Rating::new(40, 64, 10_000), Rating::Hard);
+        assert_eq!(Rating::new(22, 64, 10), Rating::Hard);
+        assert_eq!(Rating::new(80, 256, 10), Rating::Fiendish);
+        assert_eq!(Rating::Easy.min_clues(36), 18);
+        assert_eq!(Rating::Fiendish.min_clues(256), 4);
+    }
+
+    #[test]
+    fn generate_keeps_to_the_target() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for (size, mode) in [(8, Mode::SemiMagic), (6, Mode::Plain)] {
+            for target in [Rating::Easy, Rating::Medium] {
+                let puzzle = generate(&mut rng, size, mode, target).unwrap();
+                let cells = size * size;
+                assert!(puzzle.rating <= target);
+                assert_eq!(
+                    puzzle.rating,
+                    Rating::new(puzzle.clues, cells, puzzle.effort)
+                );
+                assert!(puzzle.clues >= target.min_clues(cells));
+                assert!(is_tour(&puzzle.solution, mode));
+                assert_eq!(
+                    solutions(&puzzle.grid, mode, 2).unwrap(),
+                    vec![puzzle.solution]
+                );
+            }
+        }
+        assert_eq!(generate(&mut rng, 6, Mode::SemiMagic, Rating::Easy), None);
+        assert_eq!(generate(&mut rng, 17, Mode::Plain, Rating::Easy), None);
+        assert!(has_tours(8, Mode::SemiMagic) && !has_tours(9, Mode::SemiMagic));
+        assert!(SIZES.into_iter().all(|size| has_tours(size, Mode::Plain)));
+    }
+
+    #[test]
+    fn pruning_keeps_every_tour() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        for size in [7, 8] {
+            for _ in 0..5 {
+                let tour = tour(&mut rng, size, Mode::Plain).unwrap();
+                let grid = clues(&mut rng, &tour, 0.5);
+                assert_eq!(
+                    count(&grid, Mode::Plain).unwrap(),
+                    naive_count(&grid, Mode::Plain)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn semi_magic_tours_are_distinct() {
+        let tours = (0..SEMI_MAGIC.len()).map(semi_magic).collect::<Vec<_>>();
+        for (i, tour) in tours.iter().enumerate() {
+            assert!(is_tour(tour, Mode::SemiMagic));
+            for turned in symmetries(tour) {
+                assert!(is_tour(&turned, Mode::SemiMagic));
+                assert!(!tours[..i].contains(&turned));
+            }
+        }
+    }
+}
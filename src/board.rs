@@ -1,389 +1,89 @@
+use crate::hex;
 use crate::new_game_menu::{NewGameMenu, NewGameMenuMsg};
+use crate::position_panel::PositionPanel;
+use crate::replay_viewer::ReplayViewer;
+use crate::stats_panel::StatsPanel;
+use crate::storage::{self, SavedGame};
 
-use std::collections::{HashSet, VecDeque};
-use std::fmt;
+use std::rc::Rc;
 use std::time::Duration;
 
+use games::engine::history::{History, Move};
+use games::engine::metrics::Metrics;
+use games::engine::probability::{self, Probabilities};
+use games::engine::replay::{Action, Replay};
+use games::engine::solver::{self, Hint};
+use games::engine::topology::Topology;
+use games::engine::{avf, rawvf, Board, BoardCell, GameId, GameState};
 use gloo_console::debug;
+use gloo_events::EventListener;
 use gloo_timers::callback::Interval;
-use itertools::iproduct;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
 use yew::html::Scope;
-use yew::{html, Component, Context, Event, Html, MouseEvent, TouchEvent};
+use yew::{classes, html, Component, Context, Event, Html, MouseEvent, TargetCast, TouchEvent};
 
 use wasm_timer::Instant;
 
-#[derive(Clone, PartialEq, Debug, Copy)]
-enum GameState {
-    InProgress,
-    Won,
-    Lost,
-}
-
-#[derive(Clone, PartialEq)]
-struct BoardCell {
-    pub cell: u8,
-    x: usize,
-    y: usize,
-}
-
-impl fmt::Display for BoardCell {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let out = match self.flags() {
-            3 => String::from("?"),
-            2 => String::from("f"),
-            1 => String::from(" "),
-            0 => match self.value() {
-                1..=8 => self.value().to_string(),
-                15 => String::from("m"),
-                0 => String::from(" "),
-                _ => String::from(" "),
-            },
-            _ => String::from("e"),
-        };
-        write!(f, "{}", out)
-    }
-}
-
-impl fmt::Debug for BoardCell {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let out = format!(
-            "{:?}: ({}, {})",
-            (self.x, self.y),
-            self.value(),
-            self.flags()
-        );
-        write!(f, "{}", out)
-    }
-}
-
-impl BoardCell {
-    fn flags(&self) -> u8 {
-        self.cell >> 4
-    }
-    fn value(&self) -> u8 {
-        self.cell & ((1 << 4) - 1)
-    }
-    fn click(&mut self) -> bool {
-        if self.flags() == 1 {
-            self.cell = self.value();
-            //info!(format!("{:?}", self));
-            if self.value() == 0 {
-                return true;
-            }
-        }
-        false
-    }
-
-    fn flag(&mut self) -> i8 {
-        if self.flags() != 0 {
-            self.cell = self.value() + ((self.flags() % 3 + 1) << 4);
-        }
-        match self.flags() {
-            3 => -1,
-            2 => 1,
-            _ => 0,
-        }
-    }
-
-    fn render(&self, link: &Scope<AppRender>) -> Html {
-        let (x, y) = (self.x, self.y);
-        let left_click = link.callback(move |_| AppRenderMsg::Clicked(x, y, true));
-        let right_click = link.callback(move |e: MouseEvent| {
-            e.prevent_default();
-            AppRenderMsg::Clicked(x, y, false)
-        });
-        let tap_start = link.callback(move |_| AppRenderMsg::TapStart(x, y));
-        let tap_end = link.callback(move |e: TouchEvent| {
-            e.prevent_default();
-            AppRenderMsg::TapEnd(x, y)
-        });
-        let prevent_select = link.callback(|e: Event| {
-            e.prevent_default();
-            AppRenderMsg::Nothing
-        });
-        let s = match self.flags() {
-            0 => "cell1",
-            4 => "cell1",
-            1 => "cell0",
-            _ => "cell0",
-        };
-        html! {
-            <td class={s} onclick={left_click} oncontextmenu={right_click} ontouchstart={tap_start} ontouchend={tap_end} onselectstart={prevent_select.clone()} onselect={prevent_select.clone()}>{format!("{}", self)}</td>
-        }
-    }
-}
-
-#[derive(Clone, PartialEq, Debug)]
-struct Board {
-    board: Vec<Vec<BoardCell>>,
-    pub rows: u16,
-    pub cols: u16,
-    pub mines: u16,
-    pub game_state: GameState,
-    start: bool,
-    clicked_cells: usize,
-    flagged_mines: i16,
-    start_time: Option<Instant>,
-    display_time: u16,
-    flag: bool,
-}
-
-impl Board {
-    fn new(rows: u16, cols: u16, mines: u16) -> Self {
-        Board {
-            board: (0..rows as _)
-                .map(|x| {
-                    (0..cols as _)
-                        .map(|y| BoardCell {
-                            cell: (1 << 4),
-                            x,
-                            y,
-                        })
-                        .collect()
-                })
-                .collect(),
-            rows,
-            cols,
-            mines,
-            game_state: GameState::InProgress,
-            start: false,
-            clicked_cells: 0,
-            flagged_mines: 0,
-            start_time: None,
-            display_time: 0,
-            flag: false,
-        }
-    }
-
-    fn start(&mut self, x: usize, y: usize, flag: bool) {
-        //populate board
-        debug!("Fill Board");
-        let mut rng = thread_rng();
-        let _place = x * (self.cols as usize) + y;
-        debug!("Create Mines");
-        let mut places = iproduct!(-1..=1, -1..=1)
-            .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
-            .filter(|(x, y)| 0 <= *x && *x < self.rows as i32 && 0 <= *y && *y < self.cols as i32)
-            .map(|(x, y)| (x * self.cols as i32 + y) as usize)
-            .collect::<Vec<usize>>();
-        places.sort_unstable();
-        let places = {
-            let mut temp: Vec<(usize, usize)> = vec![(0, 0)];
-            let (mut start, mut len, mut next) = (
-                (self.rows * self.cols) as _,
-                0,
-                (self.rows * self.cols) as _,
-            );
-            for e in places {
-                if e == next {
-                    len += 1;
-                    next += 1;
-                } else {
-                    if start != (self.rows * self.cols) as usize {
-                        temp.push((start, len));
-                    }
-                    start = e;
-                    len = 1;
-                    next = e + 1;
-                }
-            }
-            temp.push((start, len));
-            temp.push(((self.cols * self.rows) as _, 0));
-            temp
-        };
-        //info!(format!("{:?}", places));
-        let mut pos = (0..((self.rows * self.cols) as usize
-            - places.iter().fold(0, |acc, (_, x)| acc + x))) //Counting is hard
-            .collect::<Vec<usize>>()
-            .choose_multiple(&mut rng, self.mines as _)
-            .copied()
-            .collect::<Vec<usize>>();
-        pos.sort_unstable();
-        let mut delta = 0;
-        let mut i = 0;
-        let pos = pos
-            .iter()
-            .map(|a| {
-                while places[i].0 <= (*a) + delta {
-                    delta += places[i].1;
-                    i += 1;
-                }
-                //info!(format!("{} {} {}", a, delta, i));
-                (*a) + delta * (flag as usize)
-            })
-            .map(|a| {
-                //info!(format!("a:{} x:{}", a, a/self.m));
-                (a / (self.cols as usize), a % (self.cols as usize))
-            })
-            .collect::<Vec<(usize, usize)>>();
-        //info!(format!("self.m:{}", self.m));
-        //info!(format!("pos:{:?}", pos));
-        debug!("Place Mines");
-        for (x, y) in pos {
-            self.board[x][y].cell = 15 + (self.board[x][y].flags() << 4);
-            for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                let x1 = x as i32 + dx;
-                let y1 = y as i32 + dy;
-                if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                    let x1 = x1 as usize;
-                    let y1 = y1 as usize;
-                    if self.board[x1][y1].value() != 15 {
-                        self.board[x1][y1].cell += 1;
-                        //info!(format!("({},{}): {}", x1, y1, self.board[x1][y1].flags()),);
-                    }
-                }
-            }
-        }
-        self.start = true;
-        self.start_time = Some(Instant::now());
-        debug!("Finish Board Filling");
-    }
-
-    fn flag(&mut self, x: usize, y: usize) {
-        if self.board[x][y].flags() == 0 {
-            self.click(x, y);
-        }
-        if !self.start {
-            self.start(x, y, false);
-        }
-        self.flagged_mines += self.board[x][y].flag() as i16;
-    }
-
-    fn click(&mut self, x: usize, y: usize) {
-        debug!("Clicked");
-        if !self.start {
-            self.start(x, y, true);
-        }
-        let mut q = VecDeque::new();
-        let mut set = HashSet::new();
-        debug!("Check if flagged");
-        if self.board[x][y].flags() == 0 {
-            let mut count = 0;
-            for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                let x1 = x as i32 + dx;
-                let y1 = y as i32 + dy;
-                if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                    let x1 = x1 as usize;
-                    let y1 = y1 as usize;
-                    if self.board[x1][y1].flags() == 2 {
-                        count += 1;
-                    }
-                }
-            }
-            if count == self.board[x][y].value() {
-                for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                    let x1 = x as i32 + dx;
-                    let y1 = y as i32 + dy;
-                    if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                        let x1 = x1 as usize;
-                        let y1 = y1 as usize;
-                        if self.board[x1][y1].flags() == 1 {
-                            q.push_back((x1, y1));
-                            set.insert((x1, y1));
-                        }
-                    }
-                }
-            }
-        }
-        debug!("Check if clickable");
-        if self.board[x][y].flags() == 1 {
-            q.push_back((x, y));
-            set.insert((x, y));
-        }
-        debug!("Check all discovered values");
-        //Maybe optimize in future
-        while let Some((x, y)) = q.pop_front() {
-            //BFS
-            if self.board[x][y].value() == 15 {
-                self.board[x][y].click();
-                self.game_state = GameState::Lost;
-                self.board[x][y].cell = 15 + (4 << 4);
-                return;
-            }
-            if self.board[x][y].flags() == 1 {
-                self.clicked_cells += 1;
-            }
-            if self.board[x][y].click() {
-                for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                    let x1 = x as i32 + dx;
-                    let y1 = y as i32 + dy;
-                    if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                        let x1 = x1 as usize;
-                        let y1 = y1 as usize;
-                        if self.board[x1][y1].flags() == 1 && !set.contains(&(x1, y1)) {
-                            q.push_back((x1, y1));
-                            set.insert((x1, y1));
-                        }
-                    }
-                }
-            }
-        }
-        debug!("Check if game is won");
-        if self.clicked_cells + (self.mines as usize) == (self.cols * self.rows) as usize {
-            self.game_state = GameState::Won;
-        }
-        debug!("Finish Click");
-    }
-
-    fn time(&self) -> u16 {
-        match self.game_state {
-            GameState::InProgress => (match self.start_time {
-                Some(start_time) => Instant::now() - start_time,
-                None => Duration::ZERO,
-            })
-            .as_secs()
-            .try_into()
-            .unwrap_or_default(),
-            _ => self.display_time,
-        }
-    }
-
-    fn update(&mut self) {
-        self.display_time = self.time();
-        if self.game_state != GameState::InProgress {
-            for x in 0..self.rows as _ {
-                for y in 0..self.cols as _ {
-                    if self.board[x][y].value() != 15 {
-                        self.board[x][y].click();
-                    } else if self.game_state == GameState::Won {
-                        self.board[x][y].cell = 15 + (2 << 4);
-                    } else if self.board[x][y].flags() != 4 {
-                        self.board[x][y].cell = 15;
-                    }
-                }
-            }
-        }
-    }
-}
-
-impl Default for Board {
-    fn default() -> Self {
-        Self::new(9, 9, 10)
-    }
-}
+const KEYS: &str = "Arrows/WASD/hjkl: move, Space/Enter: reveal, F: flag, C: chord, N: new game";
 
 #[derive(Debug)]
 pub enum AppRenderMsg {
     Clicked(usize, usize, bool), //(x,y,is_left)
-    Difficulty(u16, u16, u16),   //cols, rows, mines
+    Start(GameId, bool),         //game, practice
     NewGame,
+    Restart,
     Menu,
     ToggleFlag,
+    Hint,
+    ToggleProbabilities,
+    Undo,
+    Redo,
+    ShowReplay,
+    CloseReplay,
+    CloseStats,
+    Position,
+    ClosePosition,
+    LoadPosition(Board),
+    LoadReplay(Replay),
     MenuLink(Scope<NewGameMenu>),
     UpdateTime,
     TapStart(usize, usize),
     TapEnd(usize, usize),
+    Focus(bool),
+    Hover(Option<(usize, usize)>),
+    CursorMove(isize, isize), //(dx,dy)
+    CursorReveal,
+    CursorFlag,
+    CursorChord,
     Nothing,
 }
 
 pub struct AppRender {
     board: Board,
+    history: History,
+    replay: Replay,
+    replay_view: Option<Rc<Replay>>,
     new_game_menu: Option<Scope<NewGameMenu>>,
     new_game_menu_visible: bool,
+    stats_visible: bool,
+    position_visible: bool,
+    // Positions loaded from text have no replay and don't count as games
+    imported: bool,
     last_tap: (usize, usize, Option<Instant>),
+    start_time: Option<Instant>,
+    display_time: u16,
+    hint: Option<Hint>,
+    show_probabilities: bool,
+    probabilities: Option<Probabilities>,
+    metrics: Option<Metrics>,
+    cursor: Option<(usize, usize)>,
+    focused: bool,
+    hovered: Option<(usize, usize)>,
+    // Read out by screen readers through the live region
+    announcement: String,
     _clock_updater: Interval,
+    _keyboard_listener: EventListener,
 }
 
 impl Component for AppRender {
@@ -395,29 +95,67 @@ impl Component for AppRender {
             let link = ctx.link().clone();
             Interval::new(500, move || link.send_message(AppRenderMsg::UpdateTime))
         };
-        Self {
-            board: Board::default(),
+        let keyboard_listener = {
+            let link = ctx.link().clone();
+            EventListener::new(&gloo_utils::document(), "keydown", move |e| {
+                if let Some(msg) = e.dyn_ref::<KeyboardEvent>().and_then(shortcut) {
+                    e.prevent_default();
+                    link.send_message(msg);
+                }
+            })
+        };
+        let settings = storage::load_settings();
+        let mut board = Board::from(settings.game_id());
+        board.flag = settings.flag;
+        let mut app = Self {
+            replay: Replay::new(board.game_id()),
+            replay_view: None,
+            board,
+            history: History::new(settings.practice),
             new_game_menu: None,
             new_game_menu_visible: false,
+            stats_visible: false,
+            position_visible: false,
+            imported: false,
             last_tap: (0, 0, None),
+            start_time: None,
+            display_time: 0,
+            hint: None,
+            show_probabilities: false,
+            probabilities: None,
+            metrics: None,
+            cursor: None,
+            focused: false,
+            hovered: None,
+            announcement: String::new(),
             _clock_updater: clock_handle,
+            _keyboard_listener: keyboard_listener,
+        };
+        if let Some(saved) = storage::load_game() {
+            app.restore(saved);
         }
+        app
     }
 
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         let debug_msg = format!("{:?}", msg);
-        let time = if let Some(start_time) = self.board.start_time {
-            Instant::now() - start_time
-        } else {
-            Duration::ZERO
-        };
+        let time = self.elapsed();
         debug!(format!("Starting: {} ({})", debug_msg, time.as_millis()));
+        let board_changed = !matches!(
+            msg,
+            AppRenderMsg::UpdateTime
+                | AppRenderMsg::TapStart(..)
+                | AppRenderMsg::Focus(..)
+                | AppRenderMsg::Hover(..)
+                | AppRenderMsg::CursorMove(..)
+                | AppRenderMsg::Nothing
+        );
+        let state = self.board.game_state;
         match (msg, self.board.game_state, self.new_game_menu_visible) {
             (AppRenderMsg::Clicked(x, y, flag), GameState::InProgress, false) => {
-                match flag ^ self.board.flag {
-                    true => self.board.click(x, y),
-                    false => self.board.flag(x, y),
-                }
+                self.hint = None;
+                self.cursor = Some((x, y));
+                self.play(x, y, flag);
             }
             (AppRenderMsg::NewGame, _, _) => {
                 if let Some(menu) = self.new_game_menu.as_ref() {
@@ -425,12 +163,62 @@ impl Component for AppRender {
                 }
                 self.new_game_menu_visible = true;
             }
-            (AppRenderMsg::Menu, _, _) => (),
-            (AppRenderMsg::ToggleFlag, _, false) => self.board.flag ^= true,
+            (AppRenderMsg::Restart, _, false) => {
+                let game_id = GameId {
+                    seed: rand::random(),
+                    ..self.board.game_id()
+                };
+                self.new_board(Board::from(game_id), self.history.practice);
+            }
+            (AppRenderMsg::Menu, _, false) => self.stats_visible ^= true,
+            (AppRenderMsg::CloseStats, _, _) => self.stats_visible = false,
+            (AppRenderMsg::Position, _, false) => self.position_visible ^= true,
+            (AppRenderMsg::ClosePosition, _, _) => self.position_visible = false,
+            (AppRenderMsg::LoadPosition(board), _, _) => {
+                self.new_board(board, self.history.practice);
+                self.imported = true;
+                self.announcement = "Position loaded".to_string();
+            }
+            (AppRenderMsg::LoadReplay(replay), _, _) => {
+                self.position_visible = false;
+                self.replay_view = Some(Rc::new(replay));
+            }
+            (AppRenderMsg::ToggleFlag, _, false) => {
+                self.board.flag ^= true;
+                let flag = self.board.flag;
+                storage::update_settings(|settings| settings.flag = flag);
+            }
+            (AppRenderMsg::Hint, GameState::InProgress, false) => {
+                let hint = solver::hint(&self.board);
+                self.announcement = hint.to_string();
+                self.hint = Some(hint);
+            }
+            (AppRenderMsg::ToggleProbabilities, _, false) => {
+                self.show_probabilities ^= true;
+            }
+            (AppRenderMsg::Undo, _, false) => {
+                self.hint = None;
+                if self.history.can_undo(&self.board) {
+                    self.history.undo(&mut self.board);
+                    self.record(Action::Undo);
+                    self.announcement = "Move undone".to_string();
+                }
+            }
+            (AppRenderMsg::Redo, _, false) => {
+                self.hint = None;
+                if self.history.can_redo(&self.board) {
+                    self.history.redo(&mut self.board);
+                    self.record(Action::Redo);
+                    self.announcement = "Move redone".to_string();
+                }
+            }
+            (AppRenderMsg::ShowReplay, GameState::Won | GameState::Lost, false) => {
+                self.replay_view = Some(Rc::new(self.replay.clone()));
+            }
+            (AppRenderMsg::CloseReplay, _, _) => self.replay_view = None,
             (AppRenderMsg::MenuLink(link), _, _) => self.new_game_menu = Some(link),
-            (AppRenderMsg::Difficulty(rows, cols, mines), _, true) => {
-                self.board = Board::new(rows, cols, mines);
-                self.new_game_menu_visible = false;
+            (AppRenderMsg::Start(game_id, practice), _, true) => {
+                self.new_board(Board::from(game_id), practice);
             }
             (AppRenderMsg::TapStart(x, y), GameState::InProgress, false) => {
                 self.last_tap = (x, y, Some(Instant::now()));
@@ -439,17 +227,78 @@ impl Component for AppRender {
                 if let Some(last_time) = self.last_tap.2 {
                     let time = (Instant::now() - last_time).as_millis();
                     if x == self.last_tap.0 && y == self.last_tap.1 {
+                        self.hint = None;
                         let flag = time < 400; //Fiddle with value
-                        match flag ^ self.board.flag {
-                            true => self.board.click(x, y),
-                            false => self.board.flag(x, y),
-                        }
+                        self.cursor = Some((x, y));
+                        self.play(x, y, flag);
                     }
                 }
             }
+            (AppRenderMsg::Focus(focused), _, _) => {
+                self.focused = focused;
+                if self.cursor.is_none() {
+                    self.cursor =
+                        Some((self.board.rows as usize / 2, self.board.cols as usize / 2));
+                }
+            }
+            (AppRenderMsg::Hover(pos), _, _) => self.hovered = pos,
+            (AppRenderMsg::CursorMove(dx, dy), _, false) => {
+                if let Some((x, y)) = self.cursor {
+                    let x = x
+                        .saturating_add_signed(dx)
+                        .min(self.board.rows as usize - 1);
+                    let y = y
+                        .saturating_add_signed(dy)
+                        .min(self.board.cols as usize - 1);
+                    self.cursor = Some((x, y));
+                }
+            }
+            (AppRenderMsg::CursorReveal, GameState::InProgress, false) => {
+                if let Some((x, y)) = self.cursor {
+                    self.hint = None;
+                    self.play_move(Move::click(&self.board, x, y));
+                }
+            }
+            (AppRenderMsg::CursorFlag, GameState::InProgress, false) => {
+                if let Some((x, y)) = self.cursor {
+                    self.hint = None;
+                    self.play_move(Move::Flag(x, y));
+                }
+            }
+            (AppRenderMsg::CursorChord, GameState::InProgress, false) => {
+                if let Some((x, y)) = self
+                    .cursor
+                    .filter(|&(x, y)| self.board.cell(x, y).flags() == 0)
+                {
+                    self.hint = None;
+                    self.play_move(Move::Chord(x, y));
+                }
+            }
             (_, _, _) => (),
         };
-        self.board.update();
+        if self.board.started() && self.start_time.is_none() {
+            self.start_time = Some(Instant::now());
+        }
+        if self.board.game_state == GameState::InProgress {
+            self.metrics = None;
+        } else if state == GameState::InProgress {
+            self.finished();
+        }
+        self.display_time = self.time();
+        if board_changed {
+            self.probabilities = match (self.show_probabilities, self.board.game_state) {
+                (true, GameState::InProgress) => probability::probabilities(&self.board),
+                _ => None,
+            };
+        }
+        storage::save_game(&SavedGame {
+            board: self.board.clone(),
+            elapsed_ms: self.elapsed_ms(),
+            practice: self.history.practice,
+            undo_used: self.history.used,
+            replay: self.replay.clone(),
+            imported: self.imported,
+        });
         debug!(format!("Finished: {} ({})", debug_msg, time.as_millis()));
         true
     }
@@ -459,61 +308,534 @@ impl Component for AppRender {
         let restart = link.callback(move |_| AppRenderMsg::NewGame);
         let toggle_flag = link.callback(move |_| AppRenderMsg::ToggleFlag);
         let menu = link.callback(move |_| AppRenderMsg::Menu);
-        let restart_button = match self.board.game_state {
-            GameState::InProgress => "R",
-            GameState::Won => ":)",
-            GameState::Lost => ":(",
+        let hint = link.callback(move |_| AppRenderMsg::Hint);
+        let toggle_probabilities = link.callback(move |_| AppRenderMsg::ToggleProbabilities);
+        let show_replay = link.callback(move |_| AppRenderMsg::ShowReplay);
+        let close_replay = link.callback(move |_| AppRenderMsg::CloseReplay);
+        let close_stats = link.callback(move |_| AppRenderMsg::CloseStats);
+        let position = link.callback(move |_| AppRenderMsg::Position);
+        let close_position = link.callback(move |_| AppRenderMsg::ClosePosition);
+        let load_position = link.callback(AppRenderMsg::LoadPosition);
+        let load_replay = link.callback(AppRenderMsg::LoadReplay);
+        let key = link.batch_callback(|e: KeyboardEvent| {
+            let msg = board_key(&e);
+            if msg.is_some() {
+                e.prevent_default();
+            }
+            msg
+        });
+        let focus = link.callback(|_| AppRenderMsg::Focus(true));
+        let blur = link.callback(|_| AppRenderMsg::Focus(false));
+        let undo = link.callback(move |_| AppRenderMsg::Undo);
+        let redo = link.callback(move |_| AppRenderMsg::Redo);
+        // The solver only knows boards with one mine per cell
+        let solver_class = match self.board.multi {
+            true => "button disabled",
+            false => "button",
+        };
+        let undo_class = match self.history.can_undo(&self.board) {
+            true => "button",
+            false => "button disabled",
+        };
+        let redo_class = match self.history.can_redo(&self.board) {
+            true => "button",
+            false => "button disabled",
         };
+        let select_game_id = link.callback(|e: MouseEvent| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                input.select();
+            }
+            AppRenderMsg::Nothing
+        });
+        let grid_label = format!(
+            "Minesweeper, {} rows by {} columns. {}",
+            self.board.rows, self.board.cols, KEYS
+        );
+        let active_cell = self
+            .cursor
+            .filter(|_| self.focused)
+            .map(|(x, y)| cell_id(x, y));
         html! {
             <>
             <div style={"position: absolute; top: 10%"}>
-                <div class={"title_bar"} style={format!("min-width: {}px",self.board.cols*32+2)}>
-                    <div class={"item"}>
-                        <div class={"button"} onclick={toggle_flag}>{"T"}</div>
+                <div class={"title_bar"} style={format!("min-width: {}px", board_width(&self.board))}>
+                    <div class={"item"} style={"display: flex; flex-direction: row;"}>
+                        <button class={"button"} aria-label={"Flag mode"} aria-pressed={self.board.flag.to_string()} onclick={toggle_flag}>{"T"}</button>
+                        <button class={solver_class} aria-label={"Hint"} disabled={self.board.multi} onclick={hint}>{"H"}</button>
+                        <button class={solver_class} aria-label={"Show mine probabilities"} aria-pressed={self.show_probabilities.to_string()} disabled={self.board.multi} onclick={toggle_probabilities}>{"P"}</button>
                     </div>
                     <div class={"item"} style={"display: flex; flex-direction: row;"}>
-                        {display(self.board.mines as i16 - self.board.flagged_mines)}
-                        <div class={"button"} onclick={restart}>{restart_button}</div>
-                        {display(self.board.time() as i16)}
+                        {display(self.board.mines_left(), "mines left")}
+                        <button class={"button"} aria-label={"New game"} onclick={restart}>{"R"}</button>
+                        {display(self.time() as i16, "seconds")}
                     </div>
-                    <div class={"item"}>
-                        <div class={"button"} onclick={menu}>{"S"}</div>
+                    <div class={"item"} style={"display: flex; flex-direction: row;"}>
+                        <button class={undo_class} title={"Undo (Ctrl+Z)"} aria-label={"Undo"} disabled={!self.history.can_undo(&self.board)} onclick={undo}>{"<"}</button>
+                        <button class={redo_class} title={"Redo (Ctrl+Y)"} aria-label={"Redo"} disabled={!self.history.can_redo(&self.board)} onclick={redo}>{">"}</button>
+                        if self.board.game_state != GameState::InProgress && !self.imported {
+                            <button class={"button"} title={"Replay"} aria-label={"Replay"} onclick={show_replay}>{"V"}</button>
+                        }
+                        <button class={"button"} title={"Statistics"} aria-label={"Statistics"} onclick={menu}>{"S"}</button>
+                        <button class={"button"} title={"Position"} aria-label={"Copy or paste a position"} onclick={position}>{"B"}</button>
                     </div>
                 </div>
-                <div>
-                    <table class={"board"}>
-                        <tbody>
-                        {board_display(&self.board.board, link)}
-                        </tbody>
-                    </table>
+                {self.metrics.map(|metrics| summary(self.board.game_state, &metrics)).unwrap_or_default()}
+                {self.hint.map(|hint| html! {<div class={"hint_text"}>{hint.to_string()}</div>}).unwrap_or_default()}
+                if self.history.used {
+                    <div class={"hint_text"}>{"Undo used"}</div>
+                }
+                <div class={classes!("board_frame", self.board.wrap.then_some("wrap"))} role={"grid"} tabindex="0" title={KEYS} aria-label={grid_label} aria-activedescendant={active_cell} onkeydown={key} onfocus={focus} onblur={blur}>
+                    {board_display(&self.board, &self.overlay(), link)}
                 </div>
+                <div class={"sr-only"} role={"status"} aria-live={"polite"}>{self.announcement.clone()}</div>
+                if self.imported {
+                    <input type={"text"} class={"game_id"} readonly=true disabled=true title={"Game ID"} value={"Loaded position, no game ID"}/>
+                } else {
+                    <input type={"text"} class={"game_id"} readonly=true title={"Game ID"} value={self.board.game_id().to_string()} onclick={select_game_id}/>
+                }
             </div>
             <NewGameMenu/>
+            if let Some(replay) = self.replay_view.clone() {
+                <ReplayViewer {replay} on_close={close_replay}/>
+            }
+            if self.stats_visible {
+                <StatsPanel on_close={close_stats}/>
+            }
+            if self.position_visible {
+                <PositionPanel text={self.board.to_text()} raw={if self.imported { None } else { rawvf::write(&self.replay) }} avf={avf::write(&self.board)} on_load={load_position} on_replay={load_replay} on_close={close_position}/>
+            }
             </>
         }
     }
 }
 
-fn display(number: i16) -> Html {
+impl AppRender {
+    fn new_board(&mut self, mut board: Board, practice: bool) {
+        board.flag = self.board.flag;
+        self.replay = Replay::new(board.game_id());
+        self.replay_view = None;
+        self.board = board;
+        self.history = History::new(practice);
+        self.start_time = None;
+        self.display_time = 0;
+        self.hint = None;
+        self.metrics = None;
+        self.imported = false;
+        self.position_visible = false;
+        self.announcement = format!(
+            "New game, {} rows by {} columns, {} mines",
+            self.board.rows, self.board.cols, self.board.mines
+        );
+        let (rows, cols) = (self.board.rows as usize, self.board.cols as usize);
+        self.cursor = self.cursor.map(|(x, y)| (x.min(rows - 1), y.min(cols - 1)));
+        self.new_game_menu_visible = false;
+    }
+
+    // Picks up a saved game, with the clock carrying on from where it stopped
+    fn restore(&mut self, saved: SavedGame) {
+        self.board = saved.board;
+        self.history = History::new(saved.practice);
+        self.history.used = saved.undo_used;
+        self.replay = saved.replay;
+        self.imported = saved.imported;
+        let elapsed = Duration::from_millis(saved.elapsed_ms);
+        self.start_time = self.board.started().then(|| Instant::now() - elapsed);
+        self.display_time = elapsed.as_secs().try_into().unwrap_or_default();
+        if self.board.game_state != GameState::InProgress && !self.imported {
+            self.metrics = Some(Metrics::new(&self.replay));
+        }
+    }
+
+    // Games where undo was used don't count towards the statistics, nor do
+    // practice games, a loss there can still be taken back
+    fn finished(&mut self) {
+        if self.imported {
+            return;
+        }
+        self.metrics = Some(Metrics::new(&self.replay));
+        if self.history.used || self.history.practice {
+            return;
+        }
+        let mut stats = storage::load_stats();
+        let won = self.board.game_state == GameState::Won;
+        stats.record(
+            &self.board.game_id(),
+            won,
+            self.elapsed().as_millis() as u64,
+        );
+        storage::save_stats(&stats);
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        match self.board.game_state {
+            GameState::InProgress => self.elapsed().as_millis() as u64,
+            _ => self.display_time as u64 * 1000,
+        }
+    }
+
+    fn play(&mut self, x: usize, y: usize, is_left: bool) {
+        let mv = match is_left ^ self.board.flag {
+            true => Move::click(&self.board, x, y),
+            false => Move::Flag(x, y),
+        };
+        self.play_move(mv);
+    }
+
+    fn play_move(&mut self, mv: Move) {
+        let ms = self.elapsed().as_millis() as u64;
+        let before = self.board.clone();
+        self.history.play(&mut self.board, mv);
+        self.announcement = announcement(&before, &self.board, mv);
+        self.replay.record(ms, Action::Play(mv), &self.board);
+    }
+
+    fn record(&mut self, action: Action) {
+        let ms = self.elapsed().as_millis() as u64;
+        self.replay.record(ms, action, &self.board);
+    }
+
+    fn overlay(&self) -> Overlay<'_> {
+        let probabilities = self.probabilities.as_ref();
+        let safest = probabilities
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flatten()
+            .fold(1.0, |acc: f64, p| acc.min(*p));
+        // Shows which cells a wrapped edge cell counts, for the hovered cell or
+        // else the cursor
+        let wrapped = self
+            .hovered
+            .or(self.cursor.filter(|_| self.focused))
+            .map(|(x, y)| self.board.wrapped_neighbours(x, y).collect())
+            .unwrap_or_default();
+        Overlay {
+            hint: match self.hint {
+                Some(Hint::Safe(pos, _) | Hint::Opening(pos)) => Some(pos),
+                _ => None,
+            },
+            probabilities,
+            safest,
+            cursor: self.cursor.filter(|_| self.focused),
+            wrapped,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        match self.start_time {
+            Some(start_time) => Instant::now() - start_time,
+            None => Duration::ZERO,
+        }
+    }
+
+    fn time(&self) -> u16 {
+        match self.board.game_state {
+            GameState::InProgress => self.elapsed().as_secs().try_into().unwrap_or_default(),
+            _ => self.display_time,
+        }
+    }
+}
+
+// End of game numbers shown under the title bar
+fn summary(state: GameState, metrics: &Metrics) -> Html {
+    let result = match state {
+        GameState::Won => ":) Won",
+        _ => ":( Lost",
+    };
+    let lines = [
+        format!("Time: {:.2}", metrics.ms as f64 / 1000.0),
+        format!("3BV: {}/{}", metrics.solved, metrics.bbbv),
+        format!(
+            "Clicks: {} ({} left, {} right, {} chord)",
+            metrics.clicks(),
+            metrics.left,
+            metrics.right,
+            metrics.chord
+        ),
+        format!("3BV/s: {:.2}", metrics.bbbv_per_second()),
+        format!("IOE: {:.2}", metrics.ioe()),
+        format!("Completion: {:.0}%", metrics.completion() * 100.0),
+    ];
     html! {
-        <div class={"display"}>{format!("{:03}", number.min(999).max(-99))}</div>
+        <div class={"summary"}>
+            <div class={"result"}>{result}</div>
+            {lines.into_iter().map(|line| html! {<div>{line}</div>}).collect::<Html>()}
+        </div>
     }
 }
 
-fn board_display(board: &[Vec<BoardCell>], link: &Scope<AppRender>) -> Html {
+// Decorations drawn on top of the cells
+struct Overlay<'a> {
+    hint: Option<(usize, usize)>,
+    probabilities: Option<&'a Probabilities>,
+    safest: f64,
+    cursor: Option<(usize, usize)>,
+    wrapped: Vec<(usize, usize)>,
+}
+
+impl Overlay<'_> {
+    fn probability(&self, cell: &BoardCell) -> Option<f64> {
+        self.probabilities.and_then(|p| p[cell.x][cell.y])
+    }
+
+    fn class(&self, cell: &BoardCell) -> String {
+        let mut class = vec![];
+        if self.hint == Some((cell.x, cell.y)) {
+            class.push("hint");
+        }
+        if self.cursor == Some((cell.x, cell.y)) {
+            class.push("cursor");
+        }
+        if self.wrapped.contains(&(cell.x, cell.y)) {
+            class.push("wrapped");
+        }
+        if let Some(p) = self.probability(cell) {
+            if p - self.safest < 1e-9 {
+                class.push("safest");
+            }
+            if cell.flags() == 1 {
+                class.push("probability");
+            }
+        }
+        class.join(" ")
+    }
+
+    fn colour(&self, cell: &BoardCell) -> Option<String> {
+        self.probability(cell)
+            .map(|p| format!("hsl({:.0}, 70%, 60%)", 120.0 * (1.0 - p)))
+    }
+
+    fn label(&self, cell: &BoardCell) -> String {
+        let mut label = cell_label(cell);
+        if self.hint == Some((cell.x, cell.y)) {
+            label += ", hint: safe";
+        }
+        if let (Some(p), 1) = (self.probability(cell), cell.flags()) {
+            label += &format!(", {:.0}% chance of a mine", p * 100.0);
+        }
+        label
+    }
+
+    fn text(&self, cell: &BoardCell) -> String {
+        match (self.probability(cell), cell.flags()) {
+            (Some(p), 1) => format!("{:.0}", p * 100.0),
+            _ => cell.to_string(),
+        }
+    }
+}
+
+fn render_cell(
+    cell: &BoardCell,
+    topology: Topology,
+    wrap: bool,
+    overlay: &Overlay,
+    link: &Scope<AppRender>,
+) -> Html {
+    let (x, y) = (cell.x, cell.y);
+    let left_click = link.callback(move |_| AppRenderMsg::Clicked(x, y, true));
+    let right_click = link.callback(move |e: MouseEvent| {
+        e.prevent_default();
+        AppRenderMsg::Clicked(x, y, false)
+    });
+    let tap_start = link.callback(move |_| AppRenderMsg::TapStart(x, y));
+    let tap_end = link.callback(move |e: TouchEvent| {
+        e.prevent_default();
+        AppRenderMsg::TapEnd(x, y)
+    });
+    let hover = wrap.then(|| link.callback(move |_| AppRenderMsg::Hover(Some((x, y)))));
+    let unhover = wrap.then(|| link.callback(|_| AppRenderMsg::Hover(None)));
+    let prevent_select = link.callback(|e: Event| {
+        e.prevent_default();
+        AppRenderMsg::Nothing
+    });
+    let s = match cell.flags() {
+        0 => "cell1",
+        4 => "cell1",
+        1 => "cell0",
+        _ => "cell0",
+    };
+    let s = format!("{} {}", s, overlay.class(cell));
+    match topology {
+        Topology::Square => {
+            let style = overlay
+                .colour(cell)
+                .map(|colour| format!("background-color: {};", colour))
+                .unwrap_or_default();
+            html! {
+                <td class={s} role={"gridcell"} id={cell_id(x, y)} aria-label={overlay.label(cell)} style={style} onmouseenter={hover} onmouseleave={unhover} onclick={left_click} oncontextmenu={right_click} ontouchstart={tap_start} ontouchend={tap_end} onselectstart={prevent_select.clone()} onselect={prevent_select.clone()}>{overlay.text(cell)}</td>
+            }
+        }
+        Topology::Hex => {
+            let style = overlay
+                .colour(cell)
+                .map(|colour| format!("fill: {};", colour))
+                .unwrap_or_default();
+            html! {
+                <g class={s} role={"gridcell"} id={cell_id(x, y)} aria-label={overlay.label(cell)} onmouseenter={hover} onmouseleave={unhover} onclick={left_click} oncontextmenu={right_click} ontouchstart={tap_start} ontouchend={tap_end} onselectstart={prevent_select.clone()} onselect={prevent_select.clone()}>{hex::shape(x, y, style, overlay.text(cell))}</g>
+            }
+        }
+    }
+}
+
+fn shortcut(e: &KeyboardEvent) -> Option<AppRenderMsg> {
+    if !(e.ctrl_key() || e.meta_key()) {
+        return None;
+    }
+    // Text fields keep their own undo and redo
+    if let Some(target) = e.target() {
+        if target.has_type::<HtmlInputElement>() || target.has_type::<HtmlTextAreaElement>() {
+            return None;
+        }
+    }
+    match (e.key().to_lowercase().as_str(), e.shift_key()) {
+        ("z", false) => Some(AppRenderMsg::Undo),
+        ("z", true) | ("y", _) => Some(AppRenderMsg::Redo),
+        _ => None,
+    }
+}
+
+fn cell_id(x: usize, y: usize) -> String {
+    format!("cell-{}-{}", x, y)
+}
+
+fn cell_label(cell: &BoardCell) -> String {
+    let state = match (cell.flags(), cell.mines(), cell.value()) {
+        (1, _, _) => "hidden".to_string(),
+        (2, _, _) => match cell.flagged_mines() {
+            1 => "flagged".to_string(),
+            n => format!("flagged for {} mines", n),
+        },
+        (3, _, _) => "marked with a question mark".to_string(),
+        (4, _, _) => "exploded mine".to_string(),
+        (_, 1, _) => "mine".to_string(),
+        (_, n, _) if n > 1 => format!("{} mines", n),
+        (_, _, 0) => "no adjacent mines".to_string(),
+        (_, _, 1) => "1 adjacent mine".to_string(),
+        (_, _, n) => format!("{} adjacent mines", n),
+    };
+    format!("row {} column {}, {}", cell.x + 1, cell.y + 1, state)
+}
+
+// What the live region says after a move
+fn announcement(before: &Board, board: &Board, mv: Move) -> String {
+    match board.game_state {
+        GameState::Won => return "You won, all safe cells revealed".to_string(),
+        GameState::Lost => return "You hit a mine, game over".to_string(),
+        GameState::InProgress => (),
+    }
+    if let Move::Flag(x, y) = mv {
+        return cell_label(board.cell(x, y));
+    }
+    let revealed = board
+        .cells()
+        .iter()
+        .flatten()
+        .filter(|cell| cell.flags() == 0 && before.cell(cell.x, cell.y).flags() != 0)
+        .collect::<Vec<_>>();
+    match revealed.as_slice() {
+        [] => "Nothing revealed".to_string(),
+        [cell] => cell_label(cell),
+        cells => format!("{} cells revealed", cells.len()),
+    }
+}
+
+// Keys for playing on the focused board
+fn board_key(e: &KeyboardEvent) -> Option<AppRenderMsg> {
+    if e.ctrl_key() || e.meta_key() || e.alt_key() {
+        return None;
+    }
+    match e.key().to_lowercase().as_str() {
+        "arrowup" | "w" | "k" => Some(AppRenderMsg::CursorMove(-1, 0)),
+        "arrowdown" | "s" | "j" => Some(AppRenderMsg::CursorMove(1, 0)),
+        "arrowleft" | "a" | "h" => Some(AppRenderMsg::CursorMove(0, -1)),
+        "arrowright" | "d" | "l" => Some(AppRenderMsg::CursorMove(0, 1)),
+        " " | "enter" => Some(AppRenderMsg::CursorReveal),
+        "f" => Some(AppRenderMsg::CursorFlag),
+        "c" => Some(AppRenderMsg::CursorChord),
+        "n" | "f2" => Some(AppRenderMsg::Restart),
+        _ => None,
+    }
+}
+
+fn display(number: i16, label: &str) -> Html {
     html! {
-       board
+        <div class={"display"} role={"img"} aria-label={format!("{} {}", number, label)}>{format!("{:03}", number.clamp(-99, 999))}</div>
+    }
+}
+
+fn board_display(board: &Board, overlay: &Overlay, link: &Scope<AppRender>) -> Html {
+    let topology = board.topology;
+    let rows = board.cells().iter().map(|row| {
+        let cells = row
             .iter()
-            .map(|row| {
-                html! {
-                    <tr>
-                    {row
-                        .iter()
-                        .map(|cell| cell.render(link))
-                        .collect::<Html>()}
-                    </tr>
-                }
-            })
-            .collect::<Html>()
+            .map(|cell| render_cell(cell, topology, board.wrap, overlay, link))
+            .collect::<Html>();
+        match topology {
+            Topology::Square => html! {<tr role={"row"}>{cells}</tr>},
+            Topology::Hex => html! {<g role={"row"}>{cells}</g>},
+        }
+    });
+    match topology {
+        Topology::Square => html! {
+            <table class={"board"} role={"presentation"}>
+                <tbody>{rows.collect::<Html>()}</tbody>
+            </table>
+        },
+        Topology::Hex => hex::svg(board.rows, board.cols, rows.collect()),
+    }
+}
+
+// Width of the board in pixels, to keep the title bar from getting narrower
+fn board_width(board: &Board) -> u16 {
+    match board.topology {
+        Topology::Square => board.cols * 32 + 2,
+        Topology::Hex => hex::width(board.cols).ceil() as u16 + 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_labels() {
+        let mut board = Board::with_mines(3, 5, &[(1, 2)]);
+        assert_eq!(cell_label(board.cell(0, 0)), "row 1 column 1, hidden");
+        board.flag(1, 2);
+        assert_eq!(cell_label(board.cell(1, 2)), "row 2 column 3, flagged");
+        board.click(0, 1);
+        assert_eq!(
+            cell_label(board.cell(0, 1)),
+            "row 1 column 2, 1 adjacent mine"
+        );
+    }
+
+    #[test]
+    fn announcements() {
+        let mut board = Board::with_mines(3, 5, &[(1, 2)]);
+        let before = board.clone();
+        board.click(0, 2);
+        assert_eq!(
+            announcement(&before, &board, Move::Reveal(0, 2)),
+            "row 1 column 3, 1 adjacent mine"
+        );
+        let before = board.clone();
+        board.click(0, 2);
+        assert_eq!(
+            announcement(&before, &board, Move::Chord(0, 2)),
+            "Nothing revealed"
+        );
+        let before = board.clone();
+        board.click(2, 0);
+        assert_eq!(
+            announcement(&before, &board, Move::Reveal(2, 0)),
+            "6 cells revealed"
+        );
+        let before = board.clone();
+        board.click(1, 2);
+        assert_eq!(
+            announcement(&before, &board, Move::Reveal(1, 2)),
+            "You hit a mine, game over"
+        );
     }
 }
@@ -0,0 +1,53 @@
+use yew::{html, Html};
+
+// Geometry of the hex board: pointy-top hexagons, every odd row shifted half
+// a cell to the right, like `Topology::Hex` expects.
+const RADIUS: f64 = 17.0;
+const WIDTH: f64 = RADIUS * 1.732_050_8;
+
+pub fn width(cols: u16) -> f64 {
+    WIDTH * (cols as f64 + 0.5)
+}
+
+pub fn height(rows: u16) -> f64 {
+    RADIUS * (1.5 * rows as f64 + 0.5)
+}
+
+fn centre(x: usize, y: usize) -> (f64, f64) {
+    let shift = (x % 2) as f64 * 0.5;
+    (
+        WIDTH * (y as f64 + shift + 0.5),
+        RADIUS * (1.5 * x as f64 + 1.0),
+    )
+}
+
+// The hexagon for cell (x, y) with its text, to go inside the cell's <g>.
+pub fn shape(x: usize, y: usize, style: String, text: String) -> Html {
+    let (cx, cy) = centre(x, y);
+    let points = (0..6)
+        .map(|i| {
+            let angle = std::f64::consts::PI / 3.0 * i as f64 + std::f64::consts::PI / 6.0;
+            format!(
+                "{:.1},{:.1}",
+                cx + RADIUS * angle.cos(),
+                cy + RADIUS * angle.sin()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    html! {
+        <>
+            <polygon points={points} style={style}/>
+            <text x={format!("{:.1}", cx)} y={format!("{:.1}", cy)}>{text}</text>
+        </>
+    }
+}
+
+pub fn svg(rows: u16, cols: u16, cells: Html) -> Html {
+    let (width, height) = (width(cols), height(rows));
+    html! {
+        <svg class={"board hex"} role={"presentation"} width={format!("{:.0}", width)} height={format!("{:.0}", height)} viewBox={format!("0 0 {:.1} {:.1}", width, height)}>
+            {cells}
+        </svg>
+    }
+}
@@ -0,0 +1,234 @@
+use games::solver::{self, Grid, Mode, Puzzle, Rating, SIZES};
+use js_sys::Reflect;
+use strum::IntoEnumIterator;
+use wasm_bindgen::JsValue;
+use yew::{classes, html, Component, Context, Html, InputEvent};
+
+pub enum KnightsTourMsg {
+    Input(usize, usize, String),
+    Check,
+    Hint,
+    New,
+    NextLevel,
+    NextSize,
+    NextMode,
+}
+
+pub struct KnightsTour {
+    puzzle: Puzzle,
+    entries: Grid,
+    message: String,
+    // What the next puzzle gets generated for
+    level: Rating,
+    size: usize,
+    mode: Mode,
+}
+
+impl Component for KnightsTour {
+    type Message = KnightsTourMsg;
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        let (level, size, mode) = (Rating::Medium, 8, Mode::SemiMagic);
+        let puzzle = solver::generate(&mut rand::thread_rng(), size, mode, level)
+            .expect("8x8 semi-magic puzzles start from a known tour");
+        Self {
+            entries: vec![vec![0; size]; size],
+            puzzle,
+            message: String::new(),
+            level,
+            size,
+            mode,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            KnightsTourMsg::Input(x, y, value) => {
+                let last = self.puzzle.size().pow(2) as u16;
+                self.entries[x][y] = value.trim().parse().unwrap_or(0);
+                if !(1..=last).contains(&self.entries[x][y]) {
+                    self.entries[x][y] = 0;
+                }
+                self.message = match solver::is_tour(&self.grid(), self.puzzle.mode) {
+                    true => "Solved!".to_string(),
+                    false => String::new(),
+                };
+            }
+            KnightsTourMsg::Check => self.check(),
+            KnightsTourMsg::Hint => self.hint(),
+            KnightsTourMsg::New => self.new_puzzle(),
+            KnightsTourMsg::NextLevel => {
+                self.level = Rating::iter()
+                    .cycle()
+                    .skip_while(|level| *level != self.level)
+                    .nth(1)
+                    .expect("levels should cycle");
+            }
+            // Only sizes with tours of the chosen kind come up
+            KnightsTourMsg::NextSize => {
+                self.size = SIZES
+                    .cycle()
+                    .skip_while(|&size| size != self.size)
+                    .skip(1)
+                    .find(|&size| solver::has_tours(size, self.mode))
+                    .expect("every kind of tour comes in some size");
+            }
+            KnightsTourMsg::NextMode => {
+                self.mode = Mode::iter()
+                    .cycle()
+                    .skip_while(|mode| *mode != self.mode)
+                    .nth(1)
+                    .expect("modes should cycle");
+                if !solver::has_tours(self.size, self.mode) {
+                    self.size = SIZES
+                        .into_iter()
+                        .find(|&size| solver::has_tours(size, self.mode))
+                        .expect("every kind of tour comes in some size");
+                }
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let check = link.callback(|_| KnightsTourMsg::Check);
+        let hint = link.callback(|_| KnightsTourMsg::Hint);
+        let new = link.callback(|_| KnightsTourMsg::New);
+        let next_level = link.callback(|_| KnightsTourMsg::NextLevel);
+        let next_size = link.callback(|_| KnightsTourMsg::NextSize);
+        let next_mode = link.callback(|_| KnightsTourMsg::NextMode);
+        let size = self.puzzle.size();
+        let rules = match self.puzzle.mode {
+            Mode::SemiMagic => format!(
+                "Finish the knight's tour from 1 to {}, every row and column adds up to {}",
+                size * size,
+                solver::magic_sum(size)
+            ),
+            Mode::Plain => format!("Finish the knight's tour from 1 to {}", size * size),
+        };
+        let about = format!("{}, {} clues", self.puzzle.rating, self.puzzle.clues);
+        html! {
+            <div style={"position: absolute; top: 10%"}>
+                <div class={"title_bar"}>
+                    <div class={"item"} style={"display: flex; flex-direction: row;"}>
+                        <button class={"button"} aria-label={"Check"} onclick={check}>{"C"}</button>
+                        <button class={"button"} aria-label={"Hint"} onclick={hint}>{"H"}</button>
+                    </div>
+                    <div class={"item"} style={"display: flex; flex-direction: row;"}>
+                        <button class={"button"} aria-label={"New puzzle"} onclick={new}>{"R"}</button>
+                    </div>
+                    <div class={"item"} style={"display: flex; flex-direction: row;"}>
+                        <button class={"button"} title={"Level of the next puzzle"} onclick={next_level}>{format!("Level: {}", self.level)}</button>
+                        <button class={"button"} title={"Size of the next puzzle"} onclick={next_size}>{format!("Size: {}x{}", self.size, self.size)}</button>
+                        <button class={"button"} title={"Kind of tour of the next puzzle"} onclick={next_mode}>{format!("Tour: {}", self.mode)}</button>
+                    </div>
+                </div>
+                <div class={"hint_text"}>{rules}</div>
+                <div class={"hint_text"} title={"Rated by the clues given and how hard the solver had to look"}>{about}</div>
+                <table class={"tour"}>
+                    {(0..size).map(|x| html! {
+                        <tr>{(0..size).map(|y| self.render_cell(ctx, x, y)).collect::<Html>()}</tr>
+                    }).collect::<Html>()}
+                </table>
+                <div class={"hint_text"} role={"status"} aria-live={"polite"}>{self.message.clone()}</div>
+            </div>
+        }
+    }
+}
+
+impl KnightsTour {
+    // Keeps the puzzle on the board if there's none of the chosen kind
+    fn new_puzzle(&mut self) {
+        match solver::generate(&mut rand::thread_rng(), self.size, self.mode, self.level) {
+            Some(puzzle) => {
+                self.entries = vec![vec![0; self.size]; self.size];
+                self.puzzle = puzzle;
+                self.message = String::new();
+            }
+            None => {
+                self.message = format!("No {} {}x{} tours yet", self.mode, self.size, self.size)
+            }
+        }
+    }
+
+    fn render_cell(&self, ctx: &Context<Self>, x: usize, y: usize) -> Html {
+        let shade = ((x + y) % 2 == 1).then_some("dark");
+        if self.puzzle.grid[x][y] != 0 {
+            return html! {<td class={classes!("given", shade)}>{self.puzzle.grid[x][y]}</td>};
+        }
+        let input = ctx.link().callback(move |e: InputEvent| match e.target() {
+            Some(target) => match Reflect::get(&target, &JsValue::from_str("value")) {
+                Ok(value) => KnightsTourMsg::Input(x, y, value.as_string().unwrap_or_default()),
+                Err(_) => KnightsTourMsg::Input(x, y, String::new()),
+            },
+            None => KnightsTourMsg::Input(x, y, String::new()),
+        });
+        let value = match self.entries[x][y] {
+            0 => String::new(),
+            n => n.to_string(),
+        };
+        html! {
+            <td class={classes!(shade)}>
+                <input type={"text"} inputmode={"numeric"} maxlength="3" aria-label={format!("Row {} column {}", x + 1, y + 1)} {value} oninput={input}/>
+            </td>
+        }
+    }
+
+    // The given numbers with the player's on top
+    fn grid(&self) -> Grid {
+        let mut grid = self.puzzle.grid.clone();
+        for (x, row) in grid.iter_mut().enumerate() {
+            for (y, val) in row.iter_mut().enumerate() {
+                if *val == 0 {
+                    *val = self.entries[x][y];
+                }
+            }
+        }
+        grid
+    }
+
+    fn check(&mut self) {
+        let grid = self.grid();
+        self.message = match solver::solve(&grid, self.puzzle.mode) {
+            Err(e) => format!("Can't check, {}", e),
+            Ok(Some(_)) if solver::is_tour(&grid, self.puzzle.mode) => "Solved!".to_string(),
+            Ok(Some(_)) => "No mistakes so far".to_string(),
+            Ok(None) => "Something doesn't fit".to_string(),
+        };
+    }
+
+    // Fills in the lowest missing number, or clears a wrong one if the tour
+    // can't be finished anymore.
+    fn hint(&mut self) {
+        let grid = self.grid();
+        if let Ok(Some(solution)) = solver::solve(&grid, self.puzzle.mode) {
+            let size = grid.len();
+            let next = (0..size)
+                .flat_map(|x| (0..size).map(move |y| (x, y)))
+                .filter(|&(x, y)| grid[x][y] == 0)
+                .min_by_key(|&(x, y)| solution[x][y]);
+            self.message = match next {
+                Some((x, y)) => {
+                    self.entries[x][y] = solution[x][y];
+                    format!("{} goes in row {} column {}", solution[x][y], x + 1, y + 1)
+                }
+                None => "Solved!".to_string(),
+            };
+            return;
+        }
+        let Ok(Some(solution)) = solver::solve(&self.puzzle.grid, self.puzzle.mode) else {
+            self.message = "This puzzle has no solution".to_string();
+            return;
+        };
+        let size = solution.len();
+        let wrong = (0..size)
+            .flat_map(|x| (0..size).map(move |y| (x, y)))
+            .find(|&(x, y)| self.entries[x][y] != 0 && self.entries[x][y] != solution[x][y]);
+        if let Some((x, y)) = wrong {
+            self.entries[x][y] = 0;
+            self.message = format!("Row {} column {} was wrong", x + 1, y + 1);
+        }
+    }
+}
@@ -1,23 +1,60 @@
 use yew::{html, Component, Context, Html};
 
 mod board;
+mod hex;
+mod knights_tour;
 mod new_game_menu;
+mod position_panel;
+mod replay_viewer;
+mod stats;
+mod stats_panel;
+mod storage;
 use board::AppRender;
+use knights_tour::KnightsTour;
 
-struct Model {}
+#[derive(Clone, Copy, PartialEq)]
+enum Puzzle {
+    Minesweeper,
+    KnightsTour,
+}
+
+struct Model {
+    puzzle: Puzzle,
+}
 
 impl Component for Model {
-    type Message = ();
+    type Message = Puzzle;
     type Properties = ();
 
     fn create(_ctx: &Context<Self>) -> Self {
-        Model {}
+        Model {
+            puzzle: Puzzle::Minesweeper,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, puzzle: Self::Message) -> bool {
+        self.puzzle = puzzle;
+        true
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let tab = |puzzle: Puzzle, name: &str| {
+            html! {
+                <button class={"button"} aria-pressed={(self.puzzle == puzzle).to_string()} onclick={link.callback(move |_| puzzle)}>{name}</button>
+            }
+        };
         html! {
             <>
-                <AppRender />
+                <nav class={"puzzles"}>
+                    {tab(Puzzle::Minesweeper, "Minesweeper")}
+                    {tab(Puzzle::KnightsTour, "Knight's tour")}
+                </nav>
+                if self.puzzle == Puzzle::Minesweeper {
+                    <AppRender />
+                } else {
+                    <KnightsTour />
+                }
             </>
         }
     }
@@ -1,47 +1,31 @@
 use crate::board::{AppRender, AppRenderMsg};
+use crate::storage;
 
-use std::fmt;
-
+use games::engine::difficulty::Difficulty;
+use games::engine::safety::Safety;
+use games::engine::topology::Topology;
+use games::engine::{GameId, MAX_SIDE};
 use gloo_console::log;
 use js_sys::Reflect;
-use strum::{EnumIter, IntoEnumIterator};
+use strum::IntoEnumIterator;
 use wasm_bindgen::JsValue;
 use web_sys::Event;
-use yew::{html, Component, Context, Html};
+use yew::{html, Component, Context, Html, InputEvent};
 
 pub enum NewGameMenuMsg {
     ToggleVisibility,
+    Confirm,
     Difficulty(Difficulty),
     Rows(u16),
     Cols(u16),
     Mines(u16),
-}
-
-#[derive(Copy, Clone, Debug, EnumIter, PartialEq)]
-pub enum Difficulty {
-    Easy,
-    Medium,
-    Hard,
-    Extreme,
-    Custom,
-}
-
-impl fmt::Display for Difficulty {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-impl Difficulty {
-    const fn value(&self) -> (u16, u16, u16) {
-        match self {
-            Difficulty::Easy => (9, 9, 10),
-            Difficulty::Medium => (16, 16, 40),
-            Difficulty::Hard => (16, 30, 99),
-            Difficulty::Extreme => (24, 30, 180),
-            Difficulty::Custom => (0, 0, 0),
-        }
-    }
+    GameId(String),
+    ToggleNoGuess,
+    TogglePractice,
+    NextTopology,
+    ToggleWrap,
+    ToggleMulti,
+    NextSafety,
 }
 
 pub struct NewGameMenu {
@@ -51,6 +35,13 @@ pub struct NewGameMenu {
     mines: u16,
     selected_diff: Difficulty,
     curr_diff: Difficulty,
+    game_id: String,
+    no_guess: bool,
+    practice: bool,
+    topology: Topology,
+    wrap: bool,
+    multi: bool,
+    safety: Safety,
 }
 
 impl Component for NewGameMenu {
@@ -64,13 +55,21 @@ impl Component for NewGameMenu {
             .clone()
             .downcast::<AppRender>()
             .send_message(AppRenderMsg::MenuLink(link));
+        let settings = storage::load_settings();
         Self {
             visible: false,
-            rows: 9,
-            cols: 9,
-            mines: 10,
-            selected_diff: Difficulty::Easy,
-            curr_diff: Difficulty::Easy,
+            rows: settings.rows,
+            cols: settings.cols,
+            mines: settings.mines,
+            selected_diff: settings.difficulty,
+            curr_diff: settings.difficulty,
+            game_id: String::new(),
+            no_guess: settings.no_guess,
+            practice: settings.practice,
+            topology: settings.topology,
+            wrap: settings.wrap,
+            multi: settings.multi,
+            safety: settings.safety,
         }
     }
 
@@ -78,6 +77,22 @@ impl Component for NewGameMenu {
         match msg {
             NewGameMenuMsg::ToggleVisibility => {
                 self.visible ^= true;
+                self.game_id.clear();
+            }
+            NewGameMenuMsg::Confirm => {
+                self.visible = false;
+                self.game_id.clear();
+                self.curr_diff = self.selected_diff;
+                storage::update_settings(|settings| {
+                    settings.difficulty = self.selected_diff;
+                    (settings.rows, settings.cols, settings.mines) = self.values();
+                    settings.no_guess = self.no_guess;
+                    settings.practice = self.practice;
+                    settings.topology = self.topology;
+                    settings.wrap = self.wrap;
+                    settings.multi = self.multi;
+                    settings.safety = self.safety;
+                });
             }
             NewGameMenuMsg::Difficulty(diff) => {
                 self.selected_diff = diff;
@@ -93,6 +108,35 @@ impl Component for NewGameMenu {
             NewGameMenuMsg::Mines(mines) => {
                 self.mines = mines;
             }
+            NewGameMenuMsg::GameId(game_id) => {
+                self.game_id = game_id;
+            }
+            NewGameMenuMsg::ToggleNoGuess => {
+                self.no_guess ^= true;
+            }
+            NewGameMenuMsg::TogglePractice => {
+                self.practice ^= true;
+            }
+            NewGameMenuMsg::NextTopology => {
+                self.topology = Topology::iter()
+                    .cycle()
+                    .skip_while(|topology| *topology != self.topology)
+                    .nth(1)
+                    .expect("topologies should cycle");
+            }
+            NewGameMenuMsg::ToggleWrap => {
+                self.wrap ^= true;
+            }
+            NewGameMenuMsg::ToggleMulti => {
+                self.multi ^= true;
+            }
+            NewGameMenuMsg::NextSafety => {
+                self.safety = Safety::iter()
+                    .cycle()
+                    .skip_while(|safety| *safety != self.safety)
+                    .nth(1)
+                    .expect("policies should cycle");
+            }
         }
         true
     }
@@ -106,19 +150,37 @@ impl Component for NewGameMenu {
             set_curr_diff.emit(e);
             NewGameMenuMsg::ToggleVisibility
         });
-        let close2 = link.callback(|_| NewGameMenuMsg::ToggleVisibility);
+        let confirm = link.callback(|_| NewGameMenuMsg::Confirm);
         let (rows, cols, mines) = match self.selected_diff {
             Difficulty::Custom => self.values(),
             _ => self.selected_diff.value(),
         };
+        let game_id = self.game_id.parse::<GameId>().ok();
+        let no_guess = self.no_guess;
+        let practice = self.practice;
+        let topology = self.topology;
+        let wrap = self.wrap;
+        let multi = self.multi;
+        let safety = self.safety;
         let new_game = link
             .get_parent()
             .expect("App should exit")
             .clone()
             .downcast::<AppRender>()
             .callback(move |e| {
-                close2.emit(e);
-                AppRenderMsg::Difficulty(rows, cols, mines)
+                confirm.emit(e);
+                let game_id = game_id.unwrap_or(GameId {
+                    rows,
+                    cols,
+                    mines,
+                    seed: rand::random(),
+                    no_guess,
+                    topology,
+                    wrap,
+                    multi,
+                    safety,
+                });
+                AppRenderMsg::Start(game_id, practice)
             });
         let rows_change = link.callback(|e: Event| match (e.type_().as_ref(), e.target()) {
             ("change", Some(target)) => match Reflect::get(&target, &JsValue::from_str("value")) {
@@ -168,6 +230,39 @@ impl Component for NewGameMenu {
             },
             (_, _) => NewGameMenuMsg::Mines(1),
         });
+        let game_id_change = link.callback(|e: InputEvent| match e.target() {
+            Some(target) => match Reflect::get(&target, &JsValue::from_str("value")) {
+                Ok(value) => NewGameMenuMsg::GameId(value.as_string().unwrap_or_default()),
+                Err(_) => NewGameMenuMsg::GameId(String::new()),
+            },
+            None => NewGameMenuMsg::GameId(String::new()),
+        });
+        let toggle_no_guess = link.callback(|_| NewGameMenuMsg::ToggleNoGuess);
+        let no_guess_text = if self.no_guess {
+            "No guess: On"
+        } else {
+            "No guess: Off"
+        };
+        let next_topology = link.callback(|_| NewGameMenuMsg::NextTopology);
+        let toggle_wrap = link.callback(|_| NewGameMenuMsg::ToggleWrap);
+        let wrap_text = if self.wrap { "Wrap: On" } else { "Wrap: Off" };
+        let toggle_multi = link.callback(|_| NewGameMenuMsg::ToggleMulti);
+        let multi_text = if self.multi {
+            "Multi-mine: On"
+        } else {
+            "Multi-mine: Off"
+        };
+        let next_safety = link.callback(|_| NewGameMenuMsg::NextSafety);
+        let toggle_practice = link.callback(|_| NewGameMenuMsg::TogglePractice);
+        let practice_text = if self.practice {
+            "Practice: On"
+        } else {
+            "Practice: Off"
+        };
+        let game_id_style = match (self.game_id.is_empty(), game_id) {
+            (true, _) | (false, Some(_)) => "",
+            (false, None) => "color: red;",
+        };
         html! {
             <div class={"menu"} style={format!("display: {}", display)}>
                 <div class={"item"}>
@@ -177,11 +272,18 @@ impl Component for NewGameMenu {
                 </div>
                 <div class={"item"}>
                     {Difficulty::iter().map(|x|self.create_button(ctx, x)).collect::<Html>()}
+                    <div class={"button"} onclick={toggle_no_guess}>{no_guess_text}</div>
+                    <div class={"button"} title={"Allows undoing a losing click, practice games don't count for the statistics"} onclick={toggle_practice}>{practice_text}</div>
+                    <div class={"button"} onclick={next_topology}>{format!("Grid: {}", self.topology)}</div>
+                    <div class={"button"} title={"Neighbours carry on across the edges"} onclick={toggle_wrap}>{wrap_text}</div>
+                    <div class={"button"} title={"Cells can hold up to 3 mines, no guess doesn't apply"} onclick={toggle_multi}>{multi_text}</div>
+                    <div class={"button"} title={"What the first click is kept clear of, no guess needs at least an opening"} onclick={next_safety}>{format!("First click: {}", self.safety)}</div>
                     <div style={"display: flex; flex-direction: row; justify-content: space-around;"}>
-                        <input type={"range"} id="rows" name="rows" min="5" max="60" orient="vertical" onchange={rows_change}/>
-                        <input type={"range"} id="cols" name="cols" min="5" max="60" orient="vertical" onchange={cols_change}/>
-                        <input type={"range"} id="mines" name="mines" min="1" max={format!("{}",self.max_mines())} orient="vertical" onchange={mines_change}/>
+                        <input type={"range"} id="rows" name="rows" min="5" max={MAX_SIDE.to_string()} value={self.rows.to_string()} orient="vertical" onchange={rows_change}/>
+                        <input type={"range"} id="cols" name="cols" min="5" max={MAX_SIDE.to_string()} value={self.cols.to_string()} orient="vertical" onchange={cols_change}/>
+                        <input type={"range"} id="mines" name="mines" min="1" max={format!("{}",self.max_mines())} value={self.mines.to_string()} orient="vertical" onchange={mines_change}/>
                     </div>
+                    <input type={"text"} class={"game_id"} id="game_id" name="game_id" placeholder={"Game ID"} style={game_id_style} value={self.game_id.clone()} oninput={game_id_change}/>
                 </div>
                 <div class={"item"} style={"display: flex; justify-content: space-between;"}>
                     <div class={"button"} style={"flex: 1;"} onclick={close}>{"X"}</div>
@@ -0,0 +1,166 @@
+use games::engine::replay::Replay;
+use games::engine::{avf, rawvf, Board};
+use gloo_file::callbacks::{read_as_bytes, FileReader};
+use gloo_file::{Blob, File, ObjectUrl};
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Component, Context, Event, Html, InputEvent, Properties, TargetCast};
+
+const EXAMPLE: &str = "3x4\n02F.\n02*.\n01f.";
+
+pub enum PositionPanelMsg {
+    Text(String),
+    Load,
+    Open(File),
+    Opened(Option<Vec<u8>>),
+}
+
+// What a pasted or opened position turned out to be
+enum Import {
+    Board(Board),
+    Replay(Replay),
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PositionPanelProps {
+    // The current board in the text format, if it can be written as one
+    pub text: Option<String>,
+    // The current game as a RAW video and as an Arbiter layout, if it can be
+    // written as one
+    pub raw: Option<String>,
+    pub avf: Option<Vec<u8>>,
+    pub on_load: Callback<Board>,
+    pub on_replay: Callback<Replay>,
+    pub on_close: Callback<()>,
+}
+
+pub struct PositionPanel {
+    text: String,
+    invalid: bool,
+    // Dropping these stops the read and frees the downloads
+    reader: Option<FileReader>,
+    raw_url: Option<ObjectUrl>,
+    avf_url: Option<ObjectUrl>,
+}
+
+impl Component for PositionPanel {
+    type Message = PositionPanelMsg;
+    type Properties = PositionPanelProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let props = ctx.props();
+        Self {
+            text: props.text.clone().unwrap_or_default(),
+            invalid: false,
+            reader: None,
+            raw_url: props
+                .raw
+                .as_deref()
+                .map(|raw| ObjectUrl::from(Blob::new(raw))),
+            avf_url: props
+                .avf
+                .as_deref()
+                .map(|avf| ObjectUrl::from(Blob::new(avf))),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            PositionPanelMsg::Text(text) => {
+                self.text = text;
+                self.invalid = false;
+            }
+            PositionPanelMsg::Load => self.import(ctx, import(&self.text)),
+            PositionPanelMsg::Open(file) => {
+                let link = ctx.link().clone();
+                self.reader = Some(read_as_bytes(&file, move |bytes| {
+                    link.send_message(PositionPanelMsg::Opened(bytes.ok()))
+                }));
+                return false;
+            }
+            PositionPanelMsg::Opened(bytes) => {
+                self.reader = None;
+                self.import(ctx, bytes.as_deref().and_then(open));
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_close = ctx.props().on_close.clone();
+        let close = Callback::from(move |_| on_close.emit(()));
+        let load = link.callback(|_| PositionPanelMsg::Load);
+        let text_change = link.callback(|e: InputEvent| match e.target() {
+            Some(target) => match Reflect::get(&target, &JsValue::from_str("value")) {
+                Ok(value) => PositionPanelMsg::Text(value.as_string().unwrap_or_default()),
+                Err(_) => PositionPanelMsg::Text(String::new()),
+            },
+            None => PositionPanelMsg::Text(String::new()),
+        });
+        let open = link.batch_callback(|e: Event| {
+            let file = e.target_dyn_into::<HtmlInputElement>()?.files()?.get(0)?;
+            Some(PositionPanelMsg::Open(File::from(file)))
+        });
+        let text_style = match self.invalid {
+            true => "color: red;",
+            false => "",
+        };
+        html! {
+            <div class={"menu"}>
+                <div class={"item"}>
+                    <p style={"text-align: center;"}>{"Position"}</p>
+                </div>
+                <div class={"item"}>
+                    if ctx.props().text.is_none() {
+                        <p>{"Only started boards with one mine per cell can be copied"}</p>
+                    }
+                    <p>{". hidden, * mine, F flagged mine, f wrong flag, X exploded, digits revealed"}</p>
+                    <p>{"A RAW video opens in the replay viewer, an Arbiter video loads its board"}</p>
+                    <textarea class={"position"} rows="12" spellcheck="false" aria-label={"Position"} placeholder={EXAMPLE} style={text_style} value={self.text.clone()} oninput={text_change}/>
+                    <input type={"file"} accept={".avf,.rawvf,.txt"} aria-label={"Open a video or position file"} onchange={open}/>
+                </div>
+                if self.raw_url.is_some() || self.avf_url.is_some() {
+                    <div class={"item"} style={"display: flex; justify-content: space-between;"}>
+                        if let Some(url) = &self.raw_url {
+                            <a class={"button"} style={"flex: 1;"} href={url.to_string()} download={"game.rawvf"}>{"Save RAW"}</a>
+                        }
+                        if let Some(url) = &self.avf_url {
+                            <a class={"button"} style={"flex: 1;"} href={url.to_string()} download={"board.avf"}>{"Save AVF"}</a>
+                        }
+                    </div>
+                }
+                <div class={"item"} style={"display: flex; justify-content: space-between;"}>
+                    <div class={"button"} style={"flex: 1;"} onclick={load}>{"Load"}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={close}>{"X"}</div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl PositionPanel {
+    fn import(&mut self, ctx: &Context<Self>, import: Option<Import>) {
+        match import {
+            Some(Import::Board(board)) => ctx.props().on_load.emit(board),
+            Some(Import::Replay(replay)) => ctx.props().on_replay.emit(replay),
+            None => self.invalid = true,
+        }
+    }
+}
+
+fn import(text: &str) -> Option<Import> {
+    if let Ok(board) = text.parse::<Board>() {
+        return Some(Import::Board(board));
+    }
+    rawvf::read(text).ok().map(Import::Replay)
+}
+
+// Small Arbiter videos can be valid UTF-8 too, so text gets the first try.
+fn open(bytes: &[u8]) -> Option<Import> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(import)
+        .or_else(|| avf::read(bytes).ok().map(Import::Board))
+}
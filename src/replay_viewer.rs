@@ -0,0 +1,179 @@
+use std::rc::Rc;
+
+use crate::hex;
+
+use games::engine::history::Move;
+use games::engine::replay::{Action, Player, Replay};
+use games::engine::topology::Topology;
+use games::engine::{Board, BoardCell};
+use gloo_timers::callback::Interval;
+use yew::{html, Callback, Component, Context, Html, Properties};
+
+const TICK: u64 = 50;
+const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+pub enum ReplayViewerMsg {
+    Tick,
+    TogglePause,
+    Step,
+    Restart,
+    Speed,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ReplayViewerProps {
+    pub replay: Rc<Replay>,
+    pub on_close: Callback<()>,
+}
+
+pub struct ReplayViewer {
+    player: Player,
+    time: f64,
+    playing: bool,
+    speed: usize,
+    _ticker: Interval,
+}
+
+impl Component for ReplayViewer {
+    type Message = ReplayViewerMsg;
+    type Properties = ReplayViewerProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let ticker = {
+            let link = ctx.link().clone();
+            Interval::new(TICK as u32, move || {
+                link.send_message(ReplayViewerMsg::Tick)
+            })
+        };
+        Self {
+            player: ctx.props().replay.player(),
+            time: 0.0,
+            playing: true,
+            speed: 2,
+            _ticker: ticker,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.player = ctx.props().replay.player();
+        self.time = 0.0;
+        true
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ReplayViewerMsg::Tick => {
+                if !self.playing {
+                    return false;
+                }
+                self.time += TICK as f64 * SPEEDS[self.speed];
+                self.player.advance_to(self.time as u64);
+                if self.player.finished() {
+                    self.playing = false;
+                }
+            }
+            ReplayViewerMsg::TogglePause => {
+                if self.player.finished() {
+                    self.restart();
+                }
+                self.playing ^= true;
+            }
+            ReplayViewerMsg::Step => {
+                self.playing = false;
+                if let Some((ms, _)) = self.player.step() {
+                    self.time = ms as f64;
+                }
+            }
+            ReplayViewerMsg::Restart => self.restart(),
+            ReplayViewerMsg::Speed => self.speed = (self.speed + 1) % SPEEDS.len(),
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_close = ctx.props().on_close.clone();
+        let close = Callback::from(move |_| on_close.emit(()));
+        let toggle_pause = link.callback(|_| ReplayViewerMsg::TogglePause);
+        let step = link.callback(|_| ReplayViewerMsg::Step);
+        let restart = link.callback(|_| ReplayViewerMsg::Restart);
+        let speed = link.callback(|_| ReplayViewerMsg::Speed);
+        let last = self
+            .player
+            .last_action()
+            .and_then(|(_, action)| match action {
+                Action::Play(mv) => Some(mv),
+                _ => None,
+            });
+        let duration = self.player.replay().duration();
+        html! {
+            <div class={"menu"}>
+                <div class={"item"}>
+                    <p style={"text-align: center;"}>{"Replay"}</p>
+                </div>
+                <div class={"item"} style={"display: flex; justify-content: center;"}>
+                    <div class={"display"}>{format!("{:.1}", self.time.min(duration as f64) / 1000.0)}</div>
+                    <div class={"display"}>{format!("{:.1}", duration as f64 / 1000.0)}</div>
+                </div>
+                <div class={"item"}>
+                    {render_board(&self.player.board, last)}
+                </div>
+                <div class={"item"} style={"display: flex; justify-content: space-between;"}>
+                    <div class={"button"} style={"flex: 1;"} onclick={restart}>{"|<"}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={toggle_pause}>{if self.playing { "||" } else { ">" }}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={step}>{">|"}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={speed}>{format!("x{}", SPEEDS[self.speed])}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={close}>{"X"}</div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl ReplayViewer {
+    fn restart(&mut self) {
+        self.player = self.player.replay().player();
+        self.time = 0.0;
+    }
+}
+
+fn render_board(board: &Board, last: Option<Move>) -> Html {
+    let topology = board.topology;
+    let rows = board.cells().iter().map(|row| {
+        let cells = row
+            .iter()
+            .map(|cell| render_cell(cell, topology, last))
+            .collect::<Html>();
+        match topology {
+            Topology::Square => html! {<tr>{cells}</tr>},
+            Topology::Hex => html! {<g>{cells}</g>},
+        }
+    });
+    match topology {
+        Topology::Square => html! {
+            <table class={"board"}>
+                <tbody>{rows.collect::<Html>()}</tbody>
+            </table>
+        },
+        Topology::Hex => hex::svg(board.rows, board.cols, rows.collect()),
+    }
+}
+
+fn render_cell(cell: &BoardCell, topology: Topology, last: Option<Move>) -> Html {
+    let mut class = match cell.flags() {
+        0 | 4 => "cell1",
+        _ => "cell0",
+    }
+    .to_string();
+    if last.map(|mv| mv.pos()) == Some((cell.x, cell.y)) {
+        class += " last_move";
+    }
+    match topology {
+        Topology::Square => html! {
+            <td class={class}>{cell.to_string()}</td>
+        },
+        Topology::Hex => html! {
+            <g class={class}>{hex::shape(cell.x, cell.y, String::new(), cell.to_string())}</g>
+        },
+    }
+}
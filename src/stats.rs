@@ -0,0 +1,225 @@
+use std::fmt;
+
+use games::engine::difficulty::Difficulty;
+use games::engine::safety::Safety;
+use games::engine::topology::Topology;
+use games::engine::GameId;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+// What the records are kept for: a preset, or any other size and mine count,
+// with variant games kept apart from classic ones.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum Category {
+    Preset(Difficulty),
+    Custom(u16, u16, u16),           //rows, cols, mines
+    Variant(Variant, u16, u16, u16), //variant, rows, cols, mines
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct Variant {
+    pub topology: Topology,
+    pub wrap: bool,
+    pub multi: bool,
+    pub no_guess: bool,
+    pub safety: Safety,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.topology)?;
+        if self.wrap {
+            write!(f, " wrap")?;
+        }
+        if self.multi {
+            write!(f, " multi")?;
+        }
+        if self.no_guess {
+            write!(f, " no guess")?;
+        }
+        if self.safety != Safety::default() {
+            write!(f, " {}", self.safety.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&GameId> for Category {
+    fn from(id: &GameId) -> Self {
+        let value = (id.rows, id.cols, id.mines);
+        let variant = Variant {
+            topology: id.topology,
+            wrap: id.wrap,
+            multi: id.multi,
+            no_guess: id.no_guess,
+            safety: id.safety,
+        };
+        let classic = Variant {
+            topology: Topology::Square,
+            wrap: false,
+            multi: false,
+            no_guess: false,
+            safety: Safety::default(),
+        };
+        if variant != classic {
+            return Category::Variant(variant, id.rows, id.cols, id.mines);
+        }
+        Difficulty::iter()
+            .filter(|diff| *diff != Difficulty::Custom)
+            .find(|diff| diff.value() == value)
+            .map_or(
+                Category::Custom(id.rows, id.cols, id.mines),
+                Category::Preset,
+            )
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Category::Preset(diff) => write!(f, "{}", diff),
+            Category::Custom(rows, cols, mines) => write!(f, "{}x{} {}mines", rows, cols, mines),
+            Category::Variant(variant, rows, cols, mines) => {
+                write!(f, "{} {}x{} {}mines", variant, rows, cols, mines)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Record {
+    pub played: u32,
+    pub won: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub best_ms: Option<u64>,
+    // Sum of the winning times, for the average
+    pub total_ms: u64,
+}
+
+impl Record {
+    fn add(&mut self, won: bool, ms: u64) {
+        self.played += 1;
+        if won {
+            self.won += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.best_ms = Some(self.best_ms.map_or(ms, |best| best.min(ms)));
+            self.total_ms += ms;
+        } else {
+            self.streak = 0;
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        match self.played {
+            0 => 0.0,
+            played => self.won as f64 / played as f64,
+        }
+    }
+
+    pub fn average_ms(&self) -> Option<u64> {
+        (self.won > 0).then(|| self.total_ms / self.won as u64)
+    }
+}
+
+// Finished games, counted per category.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    records: Vec<(Category, Record)>,
+}
+
+impl Stats {
+    pub fn record(&mut self, id: &GameId, won: bool, ms: u64) {
+        let category = Category::from(id);
+        let i = match self.records.binary_search_by_key(&category, |(c, _)| *c) {
+            Ok(i) => i,
+            Err(i) => {
+                self.records.insert(i, (category, Record::default()));
+                i
+            }
+        };
+        self.records[i].1.add(won, ms);
+    }
+
+    pub fn records(&self) -> &[(Category, Record)] {
+        &self.records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(rows: u16, cols: u16, mines: u16) -> GameId {
+        format!("{}x{}x{}-0", rows, cols, mines).parse().unwrap()
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(
+            Category::from(&id(16, 16, 40)),
+            Category::Preset(Difficulty::Medium)
+        );
+        assert_eq!(
+            Category::from(&id(16, 16, 41)),
+            Category::Custom(16, 16, 41)
+        );
+        // A no guess or unprotected game isn't comparable with a classic one
+        for id in ["16x16x40-0-n", "16x16x40-0-u"] {
+            assert!(matches!(
+                Category::from(&id.parse().unwrap()),
+                Category::Variant(_, 16, 16, 40)
+            ));
+        }
+        assert_eq!(
+            Category::from(&"16x16x40-0-h".parse().unwrap()),
+            Category::Variant(
+                Variant {
+                    topology: Topology::Hex,
+                    wrap: false,
+                    multi: false,
+                    no_guess: false,
+                    safety: Safety::SafeOpening,
+                },
+                16,
+                16,
+                40
+            )
+        );
+        assert_eq!(
+            Category::from(&"16x16x40-0-n-w-m-z".parse().unwrap()),
+            Category::Variant(
+                Variant {
+                    topology: Topology::Square,
+                    wrap: true,
+                    multi: true,
+                    no_guess: true,
+                    safety: Safety::ZeroOpening,
+                },
+                16,
+                16,
+                40
+            )
+        );
+    }
+
+    #[test]
+    fn records_and_streaks() {
+        let mut stats = Stats::default();
+        for (won, ms) in [(true, 9000), (true, 7000), (false, 100), (true, 11000)] {
+            stats.record(&id(9, 9, 10), won, ms);
+        }
+        stats.record(&id(10, 10, 10), true, 5000);
+        let records = stats.records();
+        assert_eq!(records.len(), 2);
+        let (category, record) = records[0];
+        assert_eq!(category, Category::Preset(Difficulty::Easy));
+        assert_eq!((record.played, record.won), (4, 3));
+        assert_eq!((record.streak, record.best_streak), (1, 2));
+        assert_eq!(record.best_ms, Some(7000));
+        assert_eq!(record.average_ms(), Some(9000));
+        assert_eq!(record.win_rate(), 0.75);
+        assert_eq!(records[1].0, Category::Custom(10, 10, 10));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::stats::{Record, Stats};
+use crate::storage;
+
+use yew::{html, Callback, Component, Context, Html, Properties};
+
+pub enum StatsPanelMsg {
+    Reset,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct StatsPanelProps {
+    pub on_close: Callback<()>,
+}
+
+pub struct StatsPanel {
+    stats: Stats,
+}
+
+impl Component for StatsPanel {
+    type Message = StatsPanelMsg;
+    type Properties = StatsPanelProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            stats: storage::load_stats(),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            StatsPanelMsg::Reset => {
+                self.stats = Stats::default();
+                storage::save_stats(&self.stats);
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_close = ctx.props().on_close.clone();
+        let close = Callback::from(move |_| on_close.emit(()));
+        let reset = link.callback(|_| StatsPanelMsg::Reset);
+        html! {
+            <div class={"menu"}>
+                <div class={"item"}>
+                    <p style={"text-align: center;"}>{"Statistics"}</p>
+                </div>
+                <div class={"item"}>
+                    if self.stats.records().is_empty() {
+                        <p>{"No games finished yet"}</p>
+                    } else {
+                        <table class={"stats"}>
+                            <thead>
+                                <tr>
+                                    <th></th>
+                                    <th>{"Played"}</th>
+                                    <th>{"Won"}</th>
+                                    <th>{"Win %"}</th>
+                                    <th>{"Streak"}</th>
+                                    <th>{"Best streak"}</th>
+                                    <th>{"Best"}</th>
+                                    <th>{"Average"}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                            {self.stats.records().iter().map(|(category, record)| html! {
+                                <tr>
+                                    <th>{category.to_string()}</th>
+                                    {row(record)}
+                                </tr>
+                            }).collect::<Html>()}
+                            </tbody>
+                        </table>
+                    }
+                </div>
+                <div class={"item"} style={"display: flex; justify-content: space-between;"}>
+                    <div class={"button"} style={"flex: 1;"} onclick={reset}>{"Reset"}</div>
+                    <div class={"button"} style={"flex: 1;"} onclick={close}>{"X"}</div>
+                </div>
+            </div>
+        }
+    }
+}
+
+fn row(record: &Record) -> Html {
+    let cells = [
+        record.played.to_string(),
+        record.won.to_string(),
+        format!("{:.0}", record.win_rate() * 100.0),
+        record.streak.to_string(),
+        record.best_streak.to_string(),
+        seconds(record.best_ms),
+        seconds(record.average_ms()),
+    ];
+    cells
+        .into_iter()
+        .map(|text| html! {<td>{text}</td>})
+        .collect()
+}
+
+fn seconds(ms: Option<u64>) -> String {
+    ms.map_or("-".to_string(), |ms| format!("{:.2}", ms as f64 / 1000.0))
+}
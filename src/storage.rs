@@ -0,0 +1,194 @@
+use crate::stats::Stats;
+
+use games::engine::difficulty::Difficulty;
+use games::engine::replay::Replay;
+use games::engine::safety::Safety;
+use games::engine::topology::Topology;
+use games::engine::{Board, GameId};
+use gloo_console::debug;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+// Everything kept in the browser's localStorage lives under these keys.
+const GAME_KEY: &str = "minesweeper.game";
+const SETTINGS_KEY: &str = "minesweeper.settings";
+const STATS_KEY: &str = "minesweeper.stats";
+
+// The game in progress, enough to pick it up again after a reload.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub board: Board,
+    pub elapsed_ms: u64,
+    pub practice: bool,
+    pub undo_used: bool,
+    pub replay: Replay,
+    // Loaded from a text position rather than played from the start
+    pub imported: bool,
+}
+
+pub fn load_game() -> Option<SavedGame> {
+    LocalStorage::get(GAME_KEY).ok()
+}
+
+pub fn save_game(game: &SavedGame) {
+    if let Err(e) = LocalStorage::set(GAME_KEY, game) {
+        debug!(format!("Couldn't save game: {}", e));
+    }
+}
+
+// The last New Game menu choices and the flag mode toggle.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub rows: u16,
+    pub cols: u16,
+    pub mines: u16,
+    pub no_guess: bool,
+    pub practice: bool,
+    pub topology: Topology,
+    pub wrap: bool,
+    pub multi: bool,
+    pub safety: Safety,
+    pub flag: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            difficulty: Difficulty::Easy,
+            rows: 9,
+            cols: 9,
+            mines: 10,
+            no_guess: false,
+            practice: false,
+            topology: Topology::Square,
+            wrap: false,
+            multi: false,
+            safety: Safety::SafeOpening,
+            flag: false,
+        }
+    }
+}
+
+impl Settings {
+    // Same bounds as the menu's sliders
+    fn valid(&self) -> bool {
+        if !(5..=60).contains(&self.rows) || !(5..=60).contains(&self.cols) {
+            return false;
+        }
+        let area = self.rows * self.cols;
+        (1..=(area / 2).min(area - 9)).contains(&self.mines)
+    }
+
+    pub fn game_id(&self) -> GameId {
+        let (rows, cols, mines) = match self.difficulty {
+            Difficulty::Custom => (self.rows, self.cols, self.mines),
+            _ => self.difficulty.value(),
+        };
+        GameId {
+            rows,
+            cols,
+            mines,
+            seed: rand::random(),
+            no_guess: self.no_guess,
+            topology: self.topology,
+            wrap: self.wrap,
+            multi: self.multi,
+            safety: self.safety,
+        }
+    }
+}
+
+pub fn load_settings() -> Settings {
+    LocalStorage::get(SETTINGS_KEY)
+        .ok()
+        .filter(Settings::valid)
+        .unwrap_or_default()
+}
+
+pub fn update_settings(f: impl FnOnce(&mut Settings)) {
+    let mut settings = load_settings();
+    f(&mut settings);
+    if let Err(e) = LocalStorage::set(SETTINGS_KEY, settings) {
+        debug!(format!("Couldn't save settings: {}", e));
+    }
+}
+
+pub fn load_stats() -> Stats {
+    LocalStorage::get(STATS_KEY).unwrap_or_default()
+}
+
+pub fn save_stats(stats: &Stats) {
+    if let Err(e) = LocalStorage::set(STATS_KEY, stats) {
+        debug!(format!("Couldn't save statistics: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use games::engine::history::Move;
+    use games::engine::replay::Action;
+
+    #[test]
+    fn games_survive_a_reload() {
+        let mut board = Board::with_seed(16, 16, 40, 7);
+        board.click(8, 8);
+        let (x, y) = board.mine_positions()[0];
+        board.flag(x, y);
+        let mut replay = Replay::new(board.game_id());
+        replay.record(1200, Action::Play(Move::Reveal(8, 8)), &board);
+        let game = SavedGame {
+            board,
+            elapsed_ms: 1500,
+            practice: true,
+            undo_used: false,
+            replay,
+            imported: true,
+        };
+        let json = serde_json::to_string(&game).unwrap();
+        let saved = serde_json::from_str::<SavedGame>(&json).unwrap();
+        assert_eq!(saved.board, game.board);
+        assert_eq!(saved.replay, game.replay);
+        assert_eq!(saved.elapsed_ms, 1500);
+        assert!(saved.practice && !saved.undo_used && saved.imported);
+    }
+
+    #[test]
+    fn settings_are_checked() {
+        let custom = Settings {
+            difficulty: Difficulty::Custom,
+            rows: 20,
+            cols: 30,
+            mines: 120,
+            ..Default::default()
+        };
+        assert!(custom.valid());
+        let id = custom.game_id();
+        assert_eq!((id.rows, id.cols, id.mines), (20, 30, 120));
+        let medium = Settings {
+            difficulty: Difficulty::Medium,
+            ..custom
+        };
+        let id = medium.game_id();
+        assert_eq!((id.rows, id.cols, id.mines), (16, 16, 40));
+        for (rows, cols, mines) in [(4, 9, 10), (9, 61, 10), (9, 9, 0), (9, 9, 41), (5, 5, 13)] {
+            let settings = Settings {
+                rows,
+                cols,
+                mines,
+                ..custom
+            };
+            assert!(!settings.valid(), "{}x{}x{}", rows, cols, mines);
+        }
+    }
+
+    #[test]
+    fn missing_settings_fall_back_to_defaults() {
+        let settings = serde_json::from_str::<Settings>(r#"{"flag":true}"#).unwrap();
+        assert!(settings.flag);
+        assert_eq!(settings.difficulty, Difficulty::Easy);
+        assert_eq!((settings.rows, settings.cols, settings.mines), (9, 9, 10));
+    }
+}